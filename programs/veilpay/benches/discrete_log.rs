@@ -0,0 +1,62 @@
+use std::num::NonZeroUsize;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use veilpay::utils::discrete_log::DecodeTable;
+
+fn threads(n: usize) -> NonZeroUsize {
+    NonZeroUsize::new(n).unwrap()
+}
+
+fn target(amount: u64) -> RistrettoPoint {
+    RistrettoPoint::mul_base(&Scalar::from(amount))
+}
+
+fn bench_table(c: &mut Criterion) {
+    c.bench_function("build_u32_table", |b| {
+        b.iter(|| DecodeTable::for_u32(threads(4)))
+    });
+
+    let table = DecodeTable::for_u32(threads(4));
+    let bytes = table.to_bytes();
+    c.bench_function("load_u32_table", |b| {
+        b.iter(|| DecodeTable::from_bytes(&bytes).unwrap())
+    });
+}
+
+fn bench_decode_u32(c: &mut Criterion) {
+    let table = DecodeTable::for_u32(threads(4));
+    let amount = u32::MAX as u64 - 1;
+
+    for n in [1, 4] {
+        c.bench_function(&format!("decode_u32_{n}_threads"), |b| {
+            b.iter_batched(
+                || target(amount),
+                |point| assert_eq!(table.decode(&point, 32, threads(n)), Some(amount)),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn bench_decode_u48(c: &mut Criterion) {
+    let table = DecodeTable::for_u48(threads(4));
+    let amount = (1u64 << 47) + 12_345;
+
+    let mut group = c.benchmark_group("decode_u48");
+    group.sample_size(10);
+    for n in [1, 4] {
+        group.bench_function(format!("{n}_threads"), |b| {
+            b.iter_batched(
+                || target(amount),
+                |point| assert_eq!(table.decode(&point, 48, threads(n)), Some(amount)),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_table, bench_decode_u32, bench_decode_u48);
+criterion_main!(benches);
@@ -2,7 +2,54 @@ pub const MINT_SEED: &[u8] = b"mint";
 pub const BALANCE_SEED: &[u8] = b"balance";
 pub const VAULT_SEED: &[u8] = b"vault";
 pub const PENDING_TRANSFER_SEED: &[u8] = b"pending_transfer";
+pub const PROOF_CONTEXT_SEED: &[u8] = b"proof_context";
+pub const STEALTH_META_ADDRESS_SEED: &[u8] = b"stealth_meta_address";
+pub const STEALTH_BALANCE_SEED: &[u8] = b"stealth_balance";
+
+/// Domain tags for the blinded owner commitment and the off-chain key derivations behind it
+pub const OWNER_COMMITMENT_DOMAIN: &[u8] = b"veilpay/owner-commitment/v1";
+pub const OWNER_SECRET_DOMAIN: &[u8] = b"veilpay/owner-secret/v1";
+pub const BALANCE_AUTHORITY_DOMAIN: &[u8] = b"veilpay/balance-authority/v1";
+/// Message a wallet signs to recover its owner secret; ed25519 signatures are deterministic
+pub const OWNER_SECRET_MESSAGE: &[u8] = b"Sign to unlock your VeilPay balances. This does not approve a transaction.";
+
+/// Domain tags for the stealth address scheme (see `utils::stealth`)
+pub const STEALTH_VIEW_KEY_DOMAIN: &[u8] = b"veilpay/stealth-view-key/v1";
+pub const STEALTH_SPEND_KEY_DOMAIN: &[u8] = b"veilpay/stealth-spend-key/v1";
+pub const STEALTH_SHARED_SECRET_DOMAIN: &[u8] = b"veilpay/stealth-shared-secret/v1";
+pub const STEALTH_VIEW_TAG_DOMAIN: &[u8] = b"veilpay/stealth-view-tag/v1";
+
+/// Domain tag for the transfer commitment hash published in PrivateTransferEvent
+pub const COMMITMENT_HASH_DOMAIN: &[u8] = b"veilpay/commitment-hash/v1";
+
+/// Layout version written by initialize_mint / update_mint_config
+pub const MINT_CONFIG_VERSION: u8 = 1;
+pub const MAX_FEE_BASIS_POINTS: u16 = 10_000;
 
 pub const ENCRYPTED_VALUE_SIZE: usize = 64;
 pub const ELGAMAL_C1_SIZE: usize = 32;
-pub const ELGAMAL_C2_SIZE: usize = 32;
\ No newline at end of file
+pub const ELGAMAL_C2_SIZE: usize = 32;
+pub const ELGAMAL_PUBKEY_SIZE: usize = 32;
+pub const AE_CIPHERTEXT_SIZE: usize = 36;
+
+/// Pedersen commitment followed by sender, recipient and auditor decrypt handles
+pub const GROUPED_ENCRYPTED_VALUE_SIZE: usize = ELGAMAL_C1_SIZE + 3 * ELGAMAL_C2_SIZE;
+pub const SENDER_HANDLE_INDEX: usize = 0;
+pub const RECIPIENT_HANDLE_INDEX: usize = 1;
+pub const AUDITOR_HANDLE_INDEX: usize = 2;
+
+/// Caps how many credits can pile up in a pending balance before the owner applies them
+pub const MAX_PENDING_BALANCE_CREDITS: u64 = 65536;
+
+/// Largest proof context the program records (batched grouped 3-handles validity)
+pub const MAX_PROOF_CONTEXT_SIZE: usize = 3 * ELGAMAL_PUBKEY_SIZE + 2 * GROUPED_ENCRYPTED_VALUE_SIZE;
+
+pub const BALANCE_BIT_LENGTH: u8 = 64;
+
+/// Transfer amounts are split into lo/hi ciphertexts so each half stays cheap to decode
+pub const TRANSFER_AMOUNT_LO_BIT_LENGTH: u8 = 16;
+pub const TRANSFER_AMOUNT_HI_BIT_LENGTH: u8 = 32;
+/// Pads the transfer range proof up to the 128 bits a BatchedRangeProofU128 covers
+pub const RANGE_PROOF_PADDING_BIT_LENGTH: u8 = 16;
+pub const MAX_TRANSFER_AMOUNT: u64 =
+    (1 << (TRANSFER_AMOUNT_LO_BIT_LENGTH + TRANSFER_AMOUNT_HI_BIT_LENGTH)) - 1;
\ No newline at end of file
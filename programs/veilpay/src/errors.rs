@@ -23,4 +23,100 @@ pub enum VeilPayError {
 
     #[msg("Invalid nonce (reply detected).")]
     InvalidNonce,
-}
\ No newline at end of file
+
+    #[msg("Required proof instruction is missing.")]
+    MissingProofInstruction,
+
+    #[msg("Proof instruction has the wrong program or proof type.")]
+    InvalidProofInstruction,
+
+    #[msg("Equality proof does not match the expected ciphertext.")]
+    EqualityProofVerificationFailed,
+
+    #[msg("Proof context account has the wrong proof type or data.")]
+    InvalidProofContext,
+
+    #[msg("Range proof does not cover the expected commitments.")]
+    RangeProofVerificationFailed,
+
+    #[msg("Validity proof does not match the transfer ciphertext.")]
+    ValidityProofVerificationFailed,
+
+    #[msg("Pubkey validity proof does not match the registered ElGamal key.")]
+    PubkeyValidityProofVerificationFailed,
+
+    #[msg("Pending balance has too many unapplied credits.")]
+    MaxPendingBalanceCreditsExceeded,
+
+    #[msg("Pending balance changed since the new available balance was computed.")]
+    PendingBalanceCreditCounterMismatch,
+
+    #[msg("Mint is paused.")]
+    MintPaused,
+
+    #[msg("Mint account was written by an unsupported config version.")]
+    UnsupportedMintVersion,
+
+    #[msg("Fee basis points exceed 100%.")]
+    InvalidFeeConfiguration,
+
+    #[msg("Instruction does not support this mint's underlying asset.")]
+    UnsupportedUnderlyingAsset,
+
+    #[msg("Account belongs to a different mint.")]
+    MintMismatch,
+
+    #[msg("Underlying mint has an extension VeilPay cannot support safely.")]
+    UnsupportedMintExtension,
+
+    #[msg("Arithmetic overflow.")]
+    ArithmeticOverflow,
+
+    #[msg("Vault holds less than the outstanding deposits.")]
+    VaultInsolvent,
+
+    #[msg("Zero balance proof does not match the available balance.")]
+    ZeroBalanceProofVerificationFailed,
+
+    #[msg("Pending balance has credits that were not applied.")]
+    PendingBalanceNotEmpty,
+
+    #[msg("Sender balance is not the sender's canonical balance PDA for this mint.")]
+    InvalidSenderBalanceAccount,
+
+    #[msg("Receiver balance is not the recipient's canonical balance PDA for this mint.")]
+    InvalidReceiverBalanceAccount,
+
+    #[msg("Sender and recipient must be different.")]
+    SelfTransferNotAllowed,
+
+    #[msg("Stealth public key is not a valid non-identity Ristretto point.")]
+    InvalidStealthPubkey,
+
+    #[msg("Account is not a stealth balance PDA for this mint.")]
+    InvalidStealthBalanceAccount,
+
+    #[msg("Stealth sweep expects (stealth balance, stealth balance payer, proof context) account triples.")]
+    InvalidStealthSweepAccounts,
+
+    #[msg("Ciphertext equality proof does not match the stealth and destination balances.")]
+    CiphertextEqualityProofVerificationFailed,
+
+    #[msg("Commitment hash does not match the transfer.")]
+    InvalidCommitmentHash,
+
+    #[msg("Ciphertext is not a valid twisted ElGamal encryption.")]
+    InvalidEncryption,
+
+    #[msg("Balance still has transfers in escrow that were not claimed or cancelled.")]
+    OpenEscrowsNotEmpty,
+
+    #[msg("Recipient balance no longer has the ElGamal key the transfer was encrypted for.")]
+    RecipientKeyMismatch,
+
+    #[msg("Rent refund account is not the payer that funded the closed account.")]
+    RentPayerMismatch,
+
+    #[msg("Fee settings cannot be changed after the mint is created.")]
+    FeeConfigurationImmutable,
+}
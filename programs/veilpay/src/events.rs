@@ -6,16 +6,17 @@ use anchor_lang::prelude::*;
 /// Privacy Design:
 /// - Only non-sensitive metadata is included
 /// - No amounts, sender, or receiver identities exposed
-/// - encrypted_tag allows recipient to detect their transactions locally
-/// - commitment_hash enables transaction verification without revealing details
+/// - encrypted_tag lets the recipient's client recognise their transfers locally
+/// - commitment_hash enables transaction verification without revealing details, and is
+///   checked on-chain against the transfer (see `generate_commitment_hash`)
 #[event]
 pub struct PrivateTransferEvent {
     /// Commitment hash for transaction verification (non-sensitive)
     /// Used for proof generation and audit trails without revealing amounts
     pub commitment_hash: [u8; 32],
     
-    /// Encrypted tag for recipient detection (only recipient can decrypt)
-    /// Generated via ECDH key derivation - only intended recipient can match
+    /// Opaque tag the sender's client attaches for the recipient to recognise the transfer
+    /// Bound by commitment_hash, but how it was derived cannot be checked on-chain
     pub encrypted_tag: [u8; 32],
     
     /// Slot number when transaction was processed
@@ -37,7 +38,7 @@ pub struct PrivateTransferEvent {
 /// Indexed by Helius for tracking new wallet initializations
 #[event]
 pub struct BalanceInitializedEvent {
-    /// Owner commitment hash (privacy-preserving identifier)
+    /// Blinded owner commitment, cannot be linked to a wallet without the owner's secret
     pub owner_commitment: [u8; 32],
     
     /// Slot number when account was initialized
@@ -45,4 +46,24 @@ pub struct BalanceInitializedEvent {
     
     /// Timestamp of initialization (Unix timestamp)
     pub timestamp: i64,
-}
\ No newline at end of file
+}
+
+/// Event emitted when a sender announces a payment to a one-time stealth key
+/// Recipients (or a scanner holding only their view key) match it against their meta-address
+#[event]
+pub struct StealthPaymentEvent {
+    /// One-time ElGamal public key the payment was made to
+    pub stealth_pubkey: [u8; 32],
+
+    /// Sender's ephemeral public key `r * G`, from which the recipient rederives the shared secret
+    pub ephemeral_pubkey: [u8; 32],
+
+    /// First byte of the shared secret hash, lets scanners reject most announcements cheaply
+    pub view_tag: u8,
+
+    /// Slot number when the payment was announced
+    pub slot: u64,
+
+    /// Timestamp of the announcement (Unix timestamp)
+    pub timestamp: i64,
+}
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+use crate::errors::VeilPayError;
+use crate::events::StealthPaymentEvent;
+use crate::utils::stealth::validate_stealth_pubkey;
+
+#[derive(Accounts)]
+pub struct AnnounceStealthPayment<'info> {
+    pub sender: Signer<'info>,
+}
+
+pub fn handler(
+    _ctx: Context<AnnounceStealthPayment>,
+    stealth_pubkey: [u8; 32],
+    ephemeral_pubkey: [u8; 32],
+    view_tag: u8,
+) -> Result<()> {
+    require!(
+        validate_stealth_pubkey(&stealth_pubkey) && validate_stealth_pubkey(&ephemeral_pubkey),
+        VeilPayError::InvalidStealthPubkey
+    );
+
+    let clock = Clock::get()?;
+    emit!(StealthPaymentEvent {
+        stealth_pubkey,
+        ephemeral_pubkey,
+        view_tag,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::VeilPayError;
+use crate::utils::{helpers::*, crypto::*};
+
+#[derive(Accounts)]
+pub struct ApplyPendingBalance<'info> {
+    #[account(
+        mut,
+        seeds = [
+            BALANCE_SEED,
+            confidential_balance.mint.as_ref(),
+            confidential_balance.owner_commitment.as_ref(),
+        ],
+        bump = confidential_balance.bump,
+        constraint = confidential_balance.owner_commitment
+            == owner_commitment(&confidential_balance.mint, &owner.key()) @ VeilPayError::UnauthorizedAccess
+    )]
+    pub confidential_balance: Account<'info, ConfidentialBalance>,
+
+    pub owner: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<ApplyPendingBalance>,
+    expected_pending_balance_credit_counter: u64,
+    new_decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+) -> Result<()> {
+    let balance = &mut ctx.accounts.confidential_balance;
+
+    // The owner computed the new AE balance over a specific set of credits; if another one
+    // landed since, the AE ciphertext would be wrong, so reject and let the client retry.
+    require!(
+        balance.pending_balance_credit_counter == expected_pending_balance_credit_counter,
+        VeilPayError::PendingBalanceCreditCounterMismatch
+    );
+
+    balance.encrypted_balance = cspl_add_with_lo_hi(
+        &balance.encrypted_balance,
+        &balance.pending_balance_lo,
+        &balance.pending_balance_hi,
+    )?;
+    balance.decryptable_available_balance = new_decryptable_available_balance;
+    balance.pending_balance_lo = ZERO_CIPHERTEXT;
+    balance.pending_balance_hi = ZERO_CIPHERTEXT;
+    balance.pending_balance_credit_counter = 0;
+
+    Ok(())
+}
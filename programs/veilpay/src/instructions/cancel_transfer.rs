@@ -8,43 +8,53 @@ use crate::errors::VeilPayError;
 pub struct CancelTransfer<'info> {
     #[account(
         mut,
-        seeds = [BALANCE_SEED, sender.key().as_ref()],
-        bump = sender_balance.bump
+        seeds = [
+            BALANCE_SEED,
+            pending_transfer.mint.as_ref(),
+            pending_transfer.sender_commitment.as_ref(),
+        ],
+        bump = sender_balance.bump,
+        constraint = sender_balance.owner_commitment
+            == owner_commitment(&pending_transfer.mint, &sender.key()) @ VeilPayError::UnauthorizedAccess
     )]
     pub sender_balance: Account<'info, ConfidentialBalance>,
 
     #[account(
         mut,
-        close = sender,
+        close = payer,
         seeds = [
             PENDING_TRANSFER_SEED, 
-            sender.key().as_ref(), // Matches sender
-            pending_transfer.recipient.as_ref(), // We trust whatever recipient is in state
-            // Again, need nonce or just verify sender.
-            // Since sender is signer, and PDA stores sender, has_one=sender ensures only original sender can cancel.
+            pending_transfer.mint.as_ref(),
+            pending_transfer.sender_commitment.as_ref(),
+            pending_transfer.recipient_commitment.as_ref(),
+            &pending_transfer.nonce.to_le_bytes(),
         ],
-        bump = pending_transfer.bump,
-        has_one = sender
+        bump = pending_transfer.bump
     )]
     pub pending_transfer: Account<'info, PendingTransfer>,
 
-    #[account(mut)]
     pub sender: Signer<'info>,
+    /// CHECK: Paid for the escrow in create_transfer and receives its rent back
+    #[account(mut, address = pending_transfer.payer)]
+    pub payer: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<CancelTransfer>) -> Result<()> {
+pub fn handler(
+    ctx: Context<CancelTransfer>,
+    new_decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+) -> Result<()> {
     let sender_balance = &mut ctx.accounts.sender_balance;
     let pending_transfer = &ctx.accounts.pending_transfer;
 
-    // 1. Decrypt current balance
-    let current_balance = cspl_decrypt(&sender_balance.encrypted_balance);
-
-    // 2. Add refunded amount
-    let new_balance = current_balance + pending_transfer.amount;
-
-    // 3. Update encrypted balance
-    sender_balance.encrypted_balance = encrypt_amount(new_balance);
+    // 1. Refund the amount homomorphically through the sender's handles
+    sender_balance.encrypted_balance = cspl_add_with_lo_hi(
+        &sender_balance.encrypted_balance,
+        &grouped_ciphertext_view(&pending_transfer.encrypted_amount_lo, SENDER_HANDLE_INDEX),
+        &grouped_ciphertext_view(&pending_transfer.encrypted_amount_hi, SENDER_HANDLE_INDEX),
+    )?;
+    sender_balance.decryptable_available_balance = new_decryptable_available_balance;
+    sender_balance.close_escrow()?;
 
     Ok(())
 }
@@ -8,35 +8,50 @@ use crate::errors::VeilPayError;
 pub struct ClaimTransfer<'info> {
     #[account(
         mut,
-        seeds = [BALANCE_SEED, recipient.key().as_ref()],
-        bump = recipient_balance.bump
+        seeds = [
+            BALANCE_SEED,
+            pending_transfer.mint.as_ref(),
+            pending_transfer.recipient_commitment.as_ref(),
+        ],
+        bump = recipient_balance.bump,
+        constraint = recipient_balance.owner_commitment
+            == owner_commitment(&pending_transfer.mint, &recipient.key()) @ VeilPayError::UnauthorizedAccess,
+        // A balance closed and re-initialized since could not decrypt the credit
+        constraint = recipient_balance.elgamal_pubkey == pending_transfer.recipient_elgamal_pubkey
+            @ VeilPayError::RecipientKeyMismatch
     )]
     pub recipient_balance: Account<'info, ConfidentialBalance>,
 
+    /// Balance the transfer was escrowed from, which tracks its open escrows
+    #[account(
+        mut,
+        seeds = [
+            BALANCE_SEED,
+            pending_transfer.mint.as_ref(),
+            pending_transfer.sender_commitment.as_ref(),
+        ],
+        bump = sender_balance.bump
+    )]
+    pub sender_balance: Account<'info, ConfidentialBalance>,
+
     #[account(
         mut,
-        close = recipient,
+        close = payer,
         seeds = [
             PENDING_TRANSFER_SEED, 
-            pending_transfer.sender.as_ref(), 
-            recipient.key().as_ref(), 
-            // We need a way to derive this seed. 
-            // IF nonce is part of the seed, the client must pass the nonce to find the PDA.
-            // But since we are passing the account directly, Anchor just validates the seeds if we specify them.
-            // Problem: The nonce was from the SENDER's state at creation time.
-            // We cannot easily re-derive the seed here without passing the nonce as an argument.
-            // So we should probably pass the nonce or rely on client to pass correct account.
-            // Actually, we can remove seeds constraint here and just verify data inside.
-            // BUT for security, we want to ensure it's a valid PDA.
-            // Let's rely on the client finding the correct address, but verify ownership inside logic.
+            pending_transfer.mint.as_ref(),
+            pending_transfer.sender_commitment.as_ref(),
+            pending_transfer.recipient_commitment.as_ref(),
+            &pending_transfer.nonce.to_le_bytes(),
         ],
-        bump = pending_transfer.bump,
-        has_one = recipient
+        bump = pending_transfer.bump
     )]
     pub pending_transfer: Account<'info, PendingTransfer>,
 
-    #[account(mut)]
     pub recipient: Signer<'info>,
+    /// CHECK: Paid for the escrow in create_transfer and receives its rent back
+    #[account(mut, address = pending_transfer.payer)]
+    pub payer: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -44,16 +59,14 @@ pub fn handler(ctx: Context<ClaimTransfer>) -> Result<()> {
     let recipient_balance = &mut ctx.accounts.recipient_balance;
     let pending_transfer = &ctx.accounts.pending_transfer;
 
-    // 1. Decrypt current balance
-    let current_balance = cspl_decrypt(&recipient_balance.encrypted_balance);
-
-    // 2. Add claimed amount
-    let new_balance = current_balance + pending_transfer.amount;
-
-    // 3. Update encrypted balance
-    recipient_balance.encrypted_balance = encrypt_amount(new_balance);
+    // 1. Credit the amount through the recipient's handles, the plaintext is never stored
+    recipient_balance.credit_pending(
+        &grouped_ciphertext_view(&pending_transfer.encrypted_amount_lo, RECIPIENT_HANDLE_INDEX),
+        &grouped_ciphertext_view(&pending_transfer.encrypted_amount_hi, RECIPIENT_HANDLE_INDEX),
+    )?;
+    ctx.accounts.sender_balance.close_escrow()?;
 
-    // 4. Update owner commitment (optional, for tracking latest update)
+    // 2. Update owner commitment (optional, for tracking latest update)
     // We might want to re-generate commitment hash/tag if we had the keys, but simpler for MVP just to update balance.
     
     Ok(())
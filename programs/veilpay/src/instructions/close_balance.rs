@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::VeilPayError;
+use crate::utils::{helpers::*, crypto::*, proof::*};
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    ZeroCiphertextProofContext, ZeroCiphertextProofData,
+};
+
+#[derive(Accounts)]
+pub struct CloseBalance<'info> {
+    #[account(
+        mut,
+        close = payer,
+        seeds = [
+            BALANCE_SEED,
+            confidential_balance.mint.as_ref(),
+            confidential_balance.owner_commitment.as_ref(),
+        ],
+        bump = confidential_balance.bump,
+        constraint = confidential_balance.owner_commitment
+            == owner_commitment(&confidential_balance.mint, &owner.key()) @ VeilPayError::UnauthorizedAccess
+    )]
+    pub confidential_balance: Account<'info, ConfidentialBalance>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: Paid for the balance in `init_balance` and gets its rent back
+    #[account(mut, address = confidential_balance.payer @ VeilPayError::RentPayerMismatch)]
+    pub payer: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, read to locate the zero ciphertext proof instruction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: Paid for the supplied proof context in `verify_proof` and gets its rent back
+    #[account(mut)]
+    pub proof_context_payer: UncheckedAccount<'info>,
+
+    /// Proof context recorded by `verify_proof`, used instead of the proof instruction when
+    /// supplied and closed once consumed
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = zero_proof_context.authority == owner.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = zero_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub zero_proof_context: Option<Account<'info, ProofContext>>,
+}
+
+pub fn handler(ctx: Context<CloseBalance>, zero_proof_instruction_offset: i8) -> Result<()> {
+    let balance = &ctx.accounts.confidential_balance;
+
+    // Credits that were never applied would be lost with the account
+    require!(
+        balance.pending_balance_credit_counter == 0,
+        VeilPayError::PendingBalanceNotEmpty
+    );
+
+    // Claim and cancel find the balance by its PDA, so a recreated one would see stale escrows
+    // and restart its nonce at a PendingTransfer address that is still taken
+    require!(
+        balance.open_escrow_count == 0,
+        VeilPayError::OpenEscrowsNotEmpty
+    );
+
+    // The available balance must encrypt zero so no funds are stranded in the vault
+    let zero_proof = verify_proof::<ZeroCiphertextProofData, ZeroCiphertextProofContext>(
+        &ctx.accounts.instructions_sysvar.to_account_info(),
+        zero_proof_instruction_offset,
+        ctx.accounts.zero_proof_context.as_deref(),
+    )?;
+    cspl_assert_zero(&balance.encrypted_balance, &balance.elgamal_pubkey, &zero_proof)?;
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::VeilPayError;
+
+#[derive(Accounts)]
+pub struct CloseProofContext<'info> {
+    #[account(
+        mut,
+        close = payer,
+        has_one = authority,
+        has_one = payer @ VeilPayError::RentPayerMismatch
+    )]
+    pub proof_context: Account<'info, ProofContext>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: Paid for the proof context in `verify_proof` and gets its rent back
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+pub fn handler(_ctx: Context<CloseProofContext>) -> Result<()> {
+    // Rent is returned to the payer by the close constraint
+    Ok(())
+}
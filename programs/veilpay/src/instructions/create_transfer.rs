@@ -1,69 +1,198 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::constants::*;
-use crate::utils::{helpers::*, crypto::*};
+use crate::utils::{helpers::*, crypto::*, proof::*};
 use crate::errors::VeilPayError;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    BatchedRangeProofContext, BatchedRangeProofU128Data,
+    CiphertextCommitmentEqualityProofContext, CiphertextCommitmentEqualityProofData,
+    BatchedGroupedCiphertext3HandlesValidityProofContext,
+    BatchedGroupedCiphertext3HandlesValidityProofData,
+};
 
 #[derive(Accounts)]
-#[instruction(amount: u64, encrypted_amount: [u8; 64], recipient: Pubkey)]
+#[instruction(
+    encrypted_amount_lo: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    recipient_commitment: [u8; 32]
+)]
 pub struct CreateTransfer<'info> {
+    #[account(
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
     #[account(
         mut,
-        seeds = [BALANCE_SEED, sender.key().as_ref()],
-        bump = sender_balance.bump
+        seeds = [
+            BALANCE_SEED,
+            veilpay_mint.key().as_ref(),
+            sender_balance.owner_commitment.as_ref(),
+        ],
+        bump = sender_balance.bump,
+        constraint = sender_balance.owner_commitment
+            == owner_commitment(&veilpay_mint.key(), &sender.key()) @ VeilPayError::UnauthorizedAccess
     )]
     pub sender_balance: Account<'info, ConfidentialBalance>,
 
+    /// Only read for the recipient's ElGamal key, the amount is credited on claim. Claim also
+    /// updates the sender's balance, so the two must differ.
+    #[account(
+        seeds = [BALANCE_SEED, veilpay_mint.key().as_ref(), recipient_commitment.as_ref()],
+        bump = recipient_balance.bump,
+        constraint = recipient_commitment != sender_balance.owner_commitment
+            @ VeilPayError::SelfTransferNotAllowed
+    )]
+    pub recipient_balance: Account<'info, ConfidentialBalance>,
+
     #[account(
         init,
-        payer = sender,
+        payer = payer,
         space = 8 + PendingTransfer::LEN,
         seeds = [
             PENDING_TRANSFER_SEED, 
-            sender.key().as_ref(), 
-            recipient.as_ref(), 
+            veilpay_mint.key().as_ref(),
+            sender_balance.owner_commitment.as_ref(),
+            recipient_commitment.as_ref(),
             &sender_balance.nonce.to_le_bytes() // Unique for each transfer
         ],
         bump
     )]
     pub pending_transfer: Account<'info, PendingTransfer>,
 
-    #[account(mut)]
     pub sender: Signer<'info>,
+    /// Funds the escrow and is refunded when it is claimed or cancelled, so the balance
+    /// authority never needs lamports of its own
+    #[account(mut)]
+    pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Instructions sysvar, read to locate the proof instructions in this transaction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: Paid for the supplied proof contexts in `verify_proof` and gets their rent back
+    #[account(mut)]
+    pub proof_context_payer: UncheckedAccount<'info>,
+
+    /// Proof contexts recorded by `verify_proof`, used instead of the matching proof
+    /// instruction when supplied and closed once consumed
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = equality_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = equality_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub equality_proof_context: Option<Account<'info, ProofContext>>,
+
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = validity_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = validity_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub validity_proof_context: Option<Account<'info, ProofContext>>,
+
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = range_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = range_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub range_proof_context: Option<Account<'info, ProofContext>>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<CreateTransfer>,
-    amount: u64,
-    encrypted_amount: [u8; 64],
-    recipient: Pubkey,
+    encrypted_amount_lo: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    recipient_commitment: [u8; 32],
+    new_decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+    equality_proof_instruction_offset: i8,
+    validity_proof_instruction_offset: i8,
+    range_proof_instruction_offset: i8,
 ) -> Result<()> {
-    let sender_balance = &mut ctx.accounts.sender_balance;
+    ctx.accounts.veilpay_mint.assert_active()?;
+
+    // 1. Subtract the escrowed amount homomorphically through the sender's handles
+    let sender_amount_lo = grouped_ciphertext_view(&encrypted_amount_lo, SENDER_HANDLE_INDEX);
+    let sender_amount_hi = grouped_ciphertext_view(&encrypted_amount_hi, SENDER_HANDLE_INDEX);
+    let new_balance = cspl_sub_with_lo_hi(
+        &ctx.accounts.sender_balance.encrypted_balance,
+        &sender_amount_lo,
+        &sender_amount_hi,
+    )?;
 
-    // 1. Verify Sender has enough funds
-    let decrypted_balance = cspl_decrypt(&sender_balance.encrypted_balance);
-    require!(
-        decrypted_balance >= amount,
-        VeilPayError::InsufficientBalance
-    );
+    // 2. The sender, recipient and auditor handles must all encrypt the same amount, since
+    //    cancel refunds through the first and claim credits through the second
+    let instructions_sysvar = ctx.accounts.instructions_sysvar.to_account_info();
+    let validity_proof = verify_proof::<
+        BatchedGroupedCiphertext3HandlesValidityProofData,
+        BatchedGroupedCiphertext3HandlesValidityProofContext,
+    >(
+        &instructions_sysvar,
+        validity_proof_instruction_offset,
+        ctx.accounts.validity_proof_context.as_deref(),
+    )?;
+    cspl_assert_valid_transfer(
+        &encrypted_amount_lo,
+        &encrypted_amount_hi,
+        &ctx.accounts.sender_balance.elgamal_pubkey,
+        &ctx.accounts.recipient_balance.elgamal_pubkey,
+        ctx.accounts.veilpay_mint.config.auditor_pubkey(),
+        &validity_proof,
+    )?;
 
-    // 2. Encrypt the amount we are subtracting (simplification for MVP: actually we should use homomorphic subtract)
-    // But since we have the plain amount, we can just subtract the plain amount and re-encrypt the new total.
-    // This removes drift and is cleaner for the MVP.
-    let new_balance = decrypted_balance - amount;
-    sender_balance.encrypted_balance = encrypt_amount(new_balance);
+    // 3. Prove the remainder is non-negative and the amount halves are in range
+    let equality_proof = verify_proof::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        &instructions_sysvar,
+        equality_proof_instruction_offset,
+        ctx.accounts.equality_proof_context.as_deref(),
+    )?;
+    let range_proof = verify_proof::<
+        BatchedRangeProofU128Data,
+        BatchedRangeProofContext,
+    >(
+        &instructions_sysvar,
+        range_proof_instruction_offset,
+        ctx.accounts.range_proof_context.as_deref(),
+    )?;
+
+    cspl_assert_ge(
+        &new_balance,
+        &sender_amount_lo,
+        &sender_amount_hi,
+        &ctx.accounts.sender_balance.elgamal_pubkey,
+        &equality_proof,
+        &range_proof,
+    )?;
+
+    let sender_balance = &mut ctx.accounts.sender_balance;
+    sender_balance.encrypted_balance = new_balance;
+    sender_balance.decryptable_available_balance = new_decryptable_available_balance;
 
-    // 3. Increment nonce to ensure unique PDA for next transfer
+    // 4. Increment nonce to ensure unique PDA for next transfer, keeping the one this PDA used
+    let nonce = sender_balance.nonce;
     sender_balance.nonce += 1;
+    sender_balance.open_escrow()?;
 
-    // 4. Initialize Pending Transfer
+    // 5. Initialize Pending Transfer
     let pending_transfer = &mut ctx.accounts.pending_transfer;
-    pending_transfer.sender = ctx.accounts.sender.key();
-    pending_transfer.recipient = recipient;
-    pending_transfer.amount = amount;
-    pending_transfer.encrypted_amount = encrypted_amount; // This is the encrypted value FOR THE RECEIVER
+    pending_transfer.mint = ctx.accounts.veilpay_mint.key();
+    pending_transfer.sender_commitment = sender_balance.owner_commitment;
+    pending_transfer.recipient_commitment = recipient_commitment;
+    pending_transfer.recipient_elgamal_pubkey = ctx.accounts.recipient_balance.elgamal_pubkey;
+    pending_transfer.encrypted_amount_lo = encrypted_amount_lo;
+    pending_transfer.encrypted_amount_hi = encrypted_amount_hi;
     pending_transfer.timestamp = Clock::get()?.unix_timestamp;
+    pending_transfer.nonce = nonce;
+    pending_transfer.payer = ctx.accounts.payer.key();
     pending_transfer.bump = ctx.bumps.pending_transfer;
 
     Ok(())
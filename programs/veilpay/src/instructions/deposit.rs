@@ -1,14 +1,29 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::constants::*;
-use crate::utils::{helpers::*, crypto::*};
-use anchor_lang::system_program;
+use crate::utils::{cpi::*, crypto::*, proof::*};
+use crate::errors::VeilPayError;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    CiphertextCommitmentEqualityProofContext, CiphertextCommitmentEqualityProofData,
+};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(
         mut,
-        seeds = [BALANCE_SEED, signer.key().as_ref()],
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
+    #[account(
+        mut,
+        seeds = [
+            BALANCE_SEED,
+            veilpay_mint.key().as_ref(),
+            confidential_balance.owner_commitment.as_ref(),
+        ],
         bump = confidential_balance.bump
     )]
     pub confidential_balance: Account<'info, ConfidentialBalance>,
@@ -24,33 +39,58 @@ pub struct Deposit<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Instructions sysvar, read to locate the equality proof instruction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
 }
 
 pub fn handler(
     ctx: Context<Deposit>,
     amount: u64,
     encrypted_amount: [u8; 64],
+    equality_proof_instruction_offset: i8,
 ) -> Result<()> {
-    // 1. Verify that encrypted_amount corresponds to amount (In real Arcium usage, this needs ZK proof)
-    // For MVP/Hackathon: We TRUST the user that encrypted_amount == amount.
-    // Ideally: verify_encryption(amount, encrypted_amount, pubkey)
-    
-    // 2. Transfer SOL from user to Vault
-    let cpi_context = CpiContext::new(
-        ctx.accounts.system_program.to_account_info(),
-        system_program::Transfer {
-            from: ctx.accounts.signer.to_account_info(),
-            to: ctx.accounts.vault.to_account_info(),
-        },
+    let veilpay_mint = &ctx.accounts.veilpay_mint;
+    veilpay_mint.assert_active()?;
+    // The lamport vault only backs the native SOL mint
+    require!(
+        veilpay_mint.underlying_asset == Pubkey::default(),
+        VeilPayError::UnsupportedUnderlyingAsset
     );
-    system_program::transfer(cpi_context, amount)?;
-
-    // 3. Update Encrypted Balance (Add)
-    ctx.accounts.confidential_balance.encrypted_balance = 
-        cspl_add(
-            &ctx.accounts.confidential_balance.encrypted_balance,
-            &encrypted_amount,
-        )?;
-        
+    require!(
+        amount <= MAX_TRANSFER_AMOUNT && amount <= veilpay_mint.config.max_deposit_amount,
+        VeilPayError::TransactionLimitExceeded
+    );
+
+    // 1. Verify that encrypted_amount encrypts exactly the deposited amount
+    let equality_proof = verify_proof_instruction::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        &ctx.accounts.instructions_sysvar.to_account_info(),
+        equality_proof_instruction_offset,
+    )?;
+    cspl_assert_eq_amount(
+        &encrypted_amount,
+        amount,
+        &ctx.accounts.confidential_balance.elgamal_pubkey,
+        &equality_proof,
+    )?;
+
+    // 2. Transfer SOL from user to Vault
+    transfer_lamports(
+        &ctx.accounts.signer.to_account_info(),
+        &ctx.accounts.vault.to_account_info(),
+        amount,
+        &[],
+    )?;
+    ctx.accounts.veilpay_mint.record_deposit(amount)?;
+
+    // 3. Credit the Pending Balance (applied later by the owner)
+    ctx.accounts
+        .confidential_balance
+        .credit_deposit(amount, &encrypted_amount)?;
+
     Ok(())
 }
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::constants::*;
+use crate::utils::{crypto::*, proof::*, token::*};
+use crate::errors::VeilPayError;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    CiphertextCommitmentEqualityProofContext, CiphertextCommitmentEqualityProofData,
+};
+
+#[derive(Accounts)]
+pub struct DepositToken<'info> {
+    #[account(
+        mut,
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
+    #[account(
+        mut,
+        seeds = [
+            BALANCE_SEED,
+            veilpay_mint.key().as_ref(),
+            confidential_balance.owner_commitment.as_ref(),
+        ],
+        bump = confidential_balance.bump
+    )]
+    pub confidential_balance: Account<'info, ConfidentialBalance>,
+
+    #[account(
+        address = veilpay_mint.underlying_asset,
+        mint::token_program = token_program
+    )]
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, veilpay_mint.key().as_ref()],
+        bump
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = underlying_mint,
+        token::authority = signer,
+        token::token_program = token_program
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub signer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Instructions sysvar, read to locate the equality proof instruction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DepositToken<'info>>,
+    amount: u64,
+    encrypted_amount: [u8; 64],
+    equality_proof_instruction_offset: i8,
+) -> Result<()> {
+    let veilpay_mint = &ctx.accounts.veilpay_mint;
+    veilpay_mint.assert_active()?;
+    require!(
+        amount <= MAX_TRANSFER_AMOUNT && amount <= veilpay_mint.config.max_deposit_amount,
+        VeilPayError::TransactionLimitExceeded
+    );
+
+    // 1. Transfer tokens from the user into the mint's vault. Transfer-hook extra accounts
+    // come in as remaining accounts.
+    let vault_amount_before = ctx.accounts.vault.amount;
+    transfer_checked_with_hook(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.user_token_account.to_account_info(),
+        &ctx.accounts.underlying_mint.to_account_info(),
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.signer.to_account_info(),
+        ctx.remaining_accounts,
+        amount,
+        ctx.accounts.underlying_mint.decimals,
+        &[],
+    )?;
+
+    // A transfer-fee mint withholds part of the amount, so only credit what the vault received
+    ctx.accounts.vault.reload()?;
+    let received_amount = ctx
+        .accounts
+        .vault
+        .amount
+        .checked_sub(vault_amount_before)
+        .ok_or(VeilPayError::ArithmeticOverflow)?;
+    ctx.accounts.veilpay_mint.record_deposit(received_amount)?;
+
+    // 2. Verify that encrypted_amount encrypts exactly the received amount
+    let equality_proof = verify_proof_instruction::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        &ctx.accounts.instructions_sysvar.to_account_info(),
+        equality_proof_instruction_offset,
+    )?;
+    cspl_assert_eq_amount(
+        &encrypted_amount,
+        received_amount,
+        &ctx.accounts.confidential_balance.elgamal_pubkey,
+        &equality_proof,
+    )?;
+
+    // 3. Credit the Pending Balance (applied later by the owner)
+    ctx.accounts
+        .confidential_balance
+        .credit_deposit(received_amount, &encrypted_amount)?;
+
+    Ok(())
+}
@@ -1,30 +1,74 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::constants::*;
-use crate::utils::helpers::*;
+use crate::utils::{helpers::*, crypto::*, proof::*};
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    PubkeyValidityProofContext, PubkeyValidityProofData,
+};
 
 #[derive(Accounts)]
 pub struct InitBalance<'info> {
+    #[account(
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
     #[account(
         init,
         payer = payer,
         space = 8 + ConfidentialBalance::LEN,
-        seeds = [BALANCE_SEED, owner.key().as_ref()],
+        seeds = [
+            BALANCE_SEED,
+            veilpay_mint.key().as_ref(),
+            &owner_commitment(&veilpay_mint.key(), &owner.key()),
+        ],
         bump
     )]
     pub confidential_balance: Account<'info, ConfidentialBalance>,
-    /// CHECK: Safe because we only use the key for seeds/commitment
-    pub owner: UncheckedAccount<'info>,
+    /// The balance authority derived from the owner's secret, never the wallet itself. It
+    /// signs so nobody else can register a key for this balance.
+    pub owner: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Instructions sysvar, read to locate the pubkey validity proof instruction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
 }
 
-pub fn handler(ctx: Context<InitBalance>) -> Result<()> {
+pub fn handler(
+    ctx: Context<InitBalance>,
+    elgamal_pubkey: [u8; ELGAMAL_PUBKEY_SIZE],
+    decryptable_zero_balance: [u8; AE_CIPHERTEXT_SIZE],
+    pubkey_validity_proof_instruction_offset: i8,
+) -> Result<()> {
+    ctx.accounts.veilpay_mint.assert_active()?;
+
+    // Owner must prove knowledge of the secret scalar behind the key
+    let pubkey_validity_proof = verify_proof_instruction::<
+        PubkeyValidityProofData,
+        PubkeyValidityProofContext,
+    >(
+        &ctx.accounts.instructions_sysvar.to_account_info(),
+        pubkey_validity_proof_instruction_offset,
+    )?;
+    cspl_assert_valid_pubkey(&elgamal_pubkey, &pubkey_validity_proof)?;
+
     let balance = &mut ctx.accounts.confidential_balance;
-    balance.owner_commitment = hash_owner(ctx.accounts.owner.key);
-    balance.encrypted_balance = [0u8; 64]; // Initialize with zero balance
+    balance.mint = ctx.accounts.veilpay_mint.key();
+    balance.owner_commitment = owner_commitment(&balance.mint, ctx.accounts.owner.key);
+    balance.elgamal_pubkey = elgamal_pubkey;
+    balance.encrypted_balance = ZERO_CIPHERTEXT; // Initialize with zero balance
+    balance.decryptable_available_balance = decryptable_zero_balance;
+    balance.pending_balance_lo = ZERO_CIPHERTEXT;
+    balance.pending_balance_hi = ZERO_CIPHERTEXT;
+    balance.pending_balance_credit_counter = 0;
+    balance.open_escrow_count = 0;
     balance.nonce = 0;
+    balance.payer = ctx.accounts.payer.key();
     balance.bump = ctx.bumps.confidential_balance;
     
     // Emit event for Helius indexing
@@ -36,4 +80,4 @@ pub fn handler(ctx: Context<InitBalance>) -> Result<()> {
     });
     
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,23 +1,65 @@
 use anchor_lang::prelude::*;
-use crate::state::VeilPayMint;
+use anchor_spl::token_interface::Mint;
+use crate::state::*;
+use crate::constants::*;
+use crate::program::Veilpay;
+use crate::errors::VeilPayError;
+use crate::utils::token::assert_supported_mint;
 
 #[derive(Accounts)]
+#[instruction(underlying_asset: Pubkey)]
 pub struct InitializeMint<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + VeilPayMint::LEN
+        space = 8 + VeilPayMint::LEN,
+        seeds = [MINT_SEED, underlying_asset.as_ref()],
+        bump
     )]
     pub veilpay_mint: Account<'info, VeilPayMint>,
+    /// Token mint backing this VeilPayMint, omitted for native SOL
+    #[account(address = underlying_asset)]
+    pub underlying_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// Only the program's upgrade authority can create mints, so nobody can squat the PDA for
+    /// an underlying asset and become its permanent authority
     #[account(mut)]
     pub authority: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Veilpay>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ VeilPayError::UnauthorizedAccess
+    )]
+    pub program_data: Account<'info, ProgramData>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<InitializeMint>, cspl_config: [u8; 64]) -> Result<()> {
+pub fn handler(
+    ctx: Context<InitializeMint>,
+    underlying_asset: Pubkey,
+    config: MintConfig,
+    genesis_hash: [u8; 32],
+) -> Result<()> {
+    config.validate()?;
+
+    if underlying_asset != Pubkey::default() {
+        let underlying_mint = ctx
+            .accounts
+            .underlying_mint
+            .as_ref()
+            .ok_or(VeilPayError::UnsupportedUnderlyingAsset)?;
+        assert_supported_mint(&underlying_mint.to_account_info())?;
+    }
+
     let mint = &mut ctx.accounts.veilpay_mint;
+    mint.version = MINT_CONFIG_VERSION;
     mint.authority = ctx.accounts.authority.key();
-    mint.cspl_config = cspl_config;
-    mint.bump = 0;
+    mint.underlying_asset = underlying_asset;
+    mint.config = config;
+    mint.total_deposited = 0;
+    mint.total_withdrawn = 0;
+    // There is no sysvar for the genesis hash, so the creator supplies it
+    mint.genesis_hash = genesis_hash;
+    mint.bump = ctx.bumps.veilpay_mint;
     Ok(())
-}
\ No newline at end of file
+}
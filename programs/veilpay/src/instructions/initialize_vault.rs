@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::constants::*;
+
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    #[account(
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
+    #[account(
+        address = veilpay_mint.underlying_asset,
+        mint::token_program = token_program
+    )]
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+
+    // Token vault backing every confidential balance of this mint, owned by the mint PDA
+    #[account(
+        init,
+        payer = payer,
+        seeds = [VAULT_SEED, veilpay_mint.key().as_ref()],
+        bump,
+        token::mint = underlying_mint,
+        token::authority = veilpay_mint,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(_ctx: Context<InitializeVault>) -> Result<()> {
+    Ok(())
+}
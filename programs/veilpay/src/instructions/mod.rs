@@ -1,8 +1,14 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod initialize_mint;
 pub mod init_balance;
 pub mod private_transfer;
 
 pub use initialize_mint::*;
+
+pub mod update_mint_config;
+pub use update_mint_config::*;
+
 pub use init_balance::*;
 pub use private_transfer::*;
 pub mod deposit;
@@ -10,6 +16,16 @@ pub mod withdraw;
 pub use deposit::*;
 pub use withdraw::*;
 
+pub mod initialize_vault;
+pub mod deposit_token;
+pub mod withdraw_token;
+pub use initialize_vault::*;
+pub use deposit_token::*;
+pub use withdraw_token::*;
+
+pub mod verify_solvency;
+pub use verify_solvency::*;
+
 pub mod create_transfer;
 pub mod claim_transfer;
 pub mod cancel_transfer;
@@ -18,5 +34,33 @@ pub use create_transfer::*;
 pub use claim_transfer::*;
 pub use cancel_transfer::*;
 
+#[cfg(feature = "debug")]
 pub mod reset_account;
-pub use reset_account::*;
\ No newline at end of file
+#[cfg(feature = "debug")]
+pub use reset_account::*;
+
+pub mod close_balance;
+pub use close_balance::*;
+
+pub mod apply_pending_balance;
+pub use apply_pending_balance::*;
+
+pub mod verify_proof;
+pub mod close_proof_context;
+
+pub use verify_proof::*;
+pub use close_proof_context::*;
+
+pub mod register_stealth_meta_address;
+pub mod update_stealth_meta_address;
+pub mod announce_stealth_payment;
+
+pub use register_stealth_meta_address::*;
+pub use update_stealth_meta_address::*;
+pub use announce_stealth_payment::*;
+
+pub mod stealth_transfer;
+pub mod sweep_stealth;
+
+pub use stealth_transfer::*;
+pub use sweep_stealth::*;
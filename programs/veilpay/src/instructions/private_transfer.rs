@@ -1,34 +1,115 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::constants::*;
 use crate::events::*;
 use crate::errors::*;
-use crate::utils::{helpers::*, crypto::*};
+use crate::utils::{helpers::*, crypto::*, proof::*};
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    BatchedRangeProofContext, BatchedRangeProofU128Data,
+    CiphertextCommitmentEqualityProofContext, CiphertextCommitmentEqualityProofData,
+    BatchedGroupedCiphertext3HandlesValidityProofContext,
+    BatchedGroupedCiphertext3HandlesValidityProofData,
+};
 
 #[derive(Accounts)]
+#[instruction(
+    encrypted_amount_lo: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    recipient_commitment: [u8; 32]
+)]
 pub struct PrivateTransfer<'info> {
+    #[account(
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = is_balance_pda(
+            &sender_balance.key(),
+            &veilpay_mint.key(),
+            &sender_balance.owner_commitment,
+            sender_balance.bump,
+        ) @ VeilPayError::InvalidSenderBalanceAccount,
+        constraint = sender_balance.mint == veilpay_mint.key() @ VeilPayError::MintMismatch,
+        constraint = sender_balance.owner_commitment
+            == owner_commitment(&veilpay_mint.key(), &sender.key())
+            @ VeilPayError::InvalidSenderBalanceAccount
+    )]
     pub sender_balance: Account<'info, ConfidentialBalance>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = is_balance_pda(
+            &receiver_balance.key(),
+            &veilpay_mint.key(),
+            &receiver_balance.owner_commitment,
+            receiver_balance.bump,
+        ) @ VeilPayError::InvalidReceiverBalanceAccount,
+        constraint = receiver_balance.mint == veilpay_mint.key() @ VeilPayError::MintMismatch,
+        constraint = receiver_balance.owner_commitment == recipient_commitment
+            @ VeilPayError::InvalidReceiverBalanceAccount
+    )]
     pub receiver_balance: Account<'info, ConfidentialBalance>,
+
+    #[account(
+        constraint = owner_commitment(&veilpay_mint.key(), &sender.key()) != recipient_commitment
+            @ VeilPayError::SelfTransferNotAllowed
+    )]
     pub sender: Signer<'info>,
+
+    /// CHECK: Instructions sysvar, read to locate the proof instructions in this transaction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: Paid for the supplied proof contexts in `verify_proof` and gets their rent back
+    #[account(mut)]
+    pub proof_context_payer: UncheckedAccount<'info>,
+
+    /// Proof contexts recorded by `verify_proof`, used instead of the matching proof
+    /// instruction when supplied and closed once consumed
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = equality_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = equality_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub equality_proof_context: Option<Account<'info, ProofContext>>,
+
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = validity_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = validity_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub validity_proof_context: Option<Account<'info, ProofContext>>,
+
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = range_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = range_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub range_proof_context: Option<Account<'info, ProofContext>>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<PrivateTransfer>,
-    encrypted_amount: [u8; 64],
+    encrypted_amount_lo: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    recipient_commitment: [u8; 32],
     expected_nonce: u64,
     commitment_hash: [u8; 32],
     encrypted_tag: [u8; 32],
+    new_decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+    equality_proof_instruction_offset: i8,
+    validity_proof_instruction_offset: i8,
+    range_proof_instruction_offset: i8,
 ) -> Result<()> {
-    let sender_key = ctx.accounts.sender.key();
-
-    // Verify commitment hash
-    require!(
-        ctx.accounts.sender_balance.owner_commitment == hash_owner(&sender_key),
-        VeilPayError::UnauthorizedAccess
-    );
+    ctx.accounts.veilpay_mint.assert_active()?;
 
     // Verify nonce
     require!(
@@ -36,27 +117,90 @@ pub fn handler(
         VeilPayError::InvalidNonce
     );
 
-    // Perform confidential Balance check
-    cspl_assert_ge(
+    // Indexers trust the published commitment, so it must describe this exact transfer
+    require!(
+        verify_commitment_hash(
+            &commitment_hash,
+            &ctx.accounts.veilpay_mint.genesis_hash,
+            &encrypted_amount_lo,
+            &encrypted_amount_hi,
+            expected_nonce,
+            &recipient_commitment,
+            &encrypted_tag,
+        ),
+        VeilPayError::InvalidCommitmentHash
+    );
+
+    // Each side only sees the amount through its own decrypt handles
+    let sender_amount_lo = grouped_ciphertext_view(&encrypted_amount_lo, SENDER_HANDLE_INDEX);
+    let sender_amount_hi = grouped_ciphertext_view(&encrypted_amount_hi, SENDER_HANDLE_INDEX);
+    let recipient_amount_lo =
+        grouped_ciphertext_view(&encrypted_amount_lo, RECIPIENT_HANDLE_INDEX);
+    let recipient_amount_hi =
+        grouped_ciphertext_view(&encrypted_amount_hi, RECIPIENT_HANDLE_INDEX);
+
+    // Homomorphic ElGamal arithmetic, the plaintext amount is never needed
+    let new_sender_balance = cspl_sub_with_lo_hi(
         &ctx.accounts.sender_balance.encrypted_balance,
-        &encrypted_amount,
+        &sender_amount_lo,
+        &sender_amount_hi,
     )?;
 
-    // Confidentially arithmetic operations (Arcium)
-    ctx.accounts.sender_balance.encrypted_balance = 
-        cspl_sub(
-            &ctx.accounts.sender_balance.encrypted_balance,
-            &encrypted_amount,
-        )?;
-
-    ctx.accounts.receiver_balance.encrypted_balance = 
-        cspl_add(
-            &ctx.accounts.receiver_balance.encrypted_balance,
-            &encrypted_amount,
-        )?;
+    // Verify all handles encrypt the same amount
+    let instructions_sysvar = ctx.accounts.instructions_sysvar.to_account_info();
+    let validity_proof = verify_proof::<
+        BatchedGroupedCiphertext3HandlesValidityProofData,
+        BatchedGroupedCiphertext3HandlesValidityProofContext,
+    >(
+        &instructions_sysvar,
+        validity_proof_instruction_offset,
+        ctx.accounts.validity_proof_context.as_deref(),
+    )?;
+    cspl_assert_valid_transfer(
+        &encrypted_amount_lo,
+        &encrypted_amount_hi,
+        &ctx.accounts.sender_balance.elgamal_pubkey,
+        &ctx.accounts.receiver_balance.elgamal_pubkey,
+        ctx.accounts.veilpay_mint.config.auditor_pubkey(),
+        &validity_proof,
+    )?;
+
+    // Perform confidential balance check against the sender's proofs
+    let equality_proof = verify_proof::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        &instructions_sysvar,
+        equality_proof_instruction_offset,
+        ctx.accounts.equality_proof_context.as_deref(),
+    )?;
+    let range_proof = verify_proof::<
+        BatchedRangeProofU128Data,
+        BatchedRangeProofContext,
+    >(
+        &instructions_sysvar,
+        range_proof_instruction_offset,
+        ctx.accounts.range_proof_context.as_deref(),
+    )?;
+
+    cspl_assert_ge(
+        &new_sender_balance,
+        &sender_amount_lo,
+        &sender_amount_hi,
+        &ctx.accounts.sender_balance.elgamal_pubkey,
+        &equality_proof,
+        &range_proof,
+    )?;
+
+    ctx.accounts.sender_balance.encrypted_balance = new_sender_balance;
+    ctx.accounts.sender_balance.decryptable_available_balance = new_decryptable_available_balance;
+
+    // Credit the receiver's pending balance so their in-flight proofs stay valid
+    ctx.accounts
+        .receiver_balance
+        .credit_pending(&recipient_amount_lo, &recipient_amount_hi)?;
         
     ctx.accounts.sender_balance.nonce += 1;    
-    ctx.accounts.receiver_balance.nonce += 1;
 
     // Emit event for Helius indexing (privacy-safe metadata only)
     let clock = Clock::get()?;
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::VeilPayError;
+use crate::utils::stealth::validate_stealth_pubkey;
+
+#[derive(Accounts)]
+pub struct RegisterStealthMetaAddress<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StealthMetaAddress::LEN,
+        seeds = [STEALTH_META_ADDRESS_SEED, owner.key().as_ref()],
+        bump
+    )]
+    pub stealth_meta_address: Account<'info, StealthMetaAddress>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<RegisterStealthMetaAddress>,
+    view_pubkey: [u8; 32],
+    spend_pubkey: [u8; 32],
+) -> Result<()> {
+    require!(
+        validate_stealth_pubkey(&view_pubkey) && validate_stealth_pubkey(&spend_pubkey),
+        VeilPayError::InvalidStealthPubkey
+    );
+
+    let meta_address = &mut ctx.accounts.stealth_meta_address;
+    meta_address.owner = ctx.accounts.owner.key();
+    meta_address.view_pubkey = view_pubkey;
+    meta_address.spend_pubkey = spend_pubkey;
+    meta_address.bump = ctx.bumps.stealth_meta_address;
+
+    Ok(())
+}
+
@@ -1,14 +1,21 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::constants::*;
-use crate::utils::{crypto::*};
+use crate::utils::{helpers::*, crypto::*};
+use crate::errors::VeilPayError;
 
 #[derive(Accounts)]
 pub struct ResetAccount<'info> {
     #[account(
         mut,
-        seeds = [BALANCE_SEED, signer.key().as_ref()],
-        bump = confidential_balance.bump
+        seeds = [
+            BALANCE_SEED,
+            confidential_balance.mint.as_ref(),
+            confidential_balance.owner_commitment.as_ref(),
+        ],
+        bump = confidential_balance.bump,
+        constraint = confidential_balance.owner_commitment
+            == owner_commitment(&confidential_balance.mint, &signer.key()) @ VeilPayError::UnauthorizedAccess
     )]
     pub confidential_balance: Account<'info, ConfidentialBalance>,
     
@@ -16,9 +23,16 @@ pub struct ResetAccount<'info> {
     pub signer: Signer<'info>,
 }
 
-pub fn handler(ctx: Context<ResetAccount>) -> Result<()> {
+pub fn handler(
+    ctx: Context<ResetAccount>,
+    decryptable_zero_balance: [u8; AE_CIPHERTEXT_SIZE],
+) -> Result<()> {
     // 1. Reset Balance to 0
-    ctx.accounts.confidential_balance.encrypted_balance = encrypt_amount(0);
+    ctx.accounts.confidential_balance.encrypted_balance = ZERO_CIPHERTEXT;
+    ctx.accounts.confidential_balance.decryptable_available_balance = decryptable_zero_balance;
+    ctx.accounts.confidential_balance.pending_balance_lo = ZERO_CIPHERTEXT;
+    ctx.accounts.confidential_balance.pending_balance_hi = ZERO_CIPHERTEXT;
+    ctx.accounts.confidential_balance.pending_balance_credit_counter = 0;
     
     // 2. Reset Nonce (Optional, but good for clean slate)
     ctx.accounts.confidential_balance.nonce = 0;
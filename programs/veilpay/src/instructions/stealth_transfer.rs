@@ -0,0 +1,202 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::events::StealthPaymentEvent;
+use crate::errors::VeilPayError;
+use crate::utils::{helpers::*, crypto::*, proof::*, stealth::validate_stealth_pubkey};
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    BatchedRangeProofContext, BatchedRangeProofU128Data,
+    CiphertextCommitmentEqualityProofContext, CiphertextCommitmentEqualityProofData,
+    BatchedGroupedCiphertext3HandlesValidityProofContext,
+    BatchedGroupedCiphertext3HandlesValidityProofData,
+};
+
+#[derive(Accounts)]
+#[instruction(
+    encrypted_amount_lo: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    stealth_pubkey: [u8; 32]
+)]
+pub struct StealthTransfer<'info> {
+    #[account(
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
+    #[account(
+        mut,
+        seeds = [
+            BALANCE_SEED,
+            veilpay_mint.key().as_ref(),
+            sender_balance.owner_commitment.as_ref(),
+        ],
+        bump = sender_balance.bump,
+        constraint = sender_balance.owner_commitment
+            == owner_commitment(&veilpay_mint.key(), &sender.key()) @ VeilPayError::UnauthorizedAccess
+    )]
+    pub sender_balance: Account<'info, ConfidentialBalance>,
+
+    /// Fresh balance for the one-time stealth key, so nothing on-chain names the recipient
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ConfidentialBalance::LEN,
+        seeds = [STEALTH_BALANCE_SEED, veilpay_mint.key().as_ref(), stealth_pubkey.as_ref()],
+        bump
+    )]
+    pub stealth_balance: Account<'info, ConfidentialBalance>,
+
+    pub sender: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Instructions sysvar, read to locate the proof instructions in this transaction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: Paid for the supplied proof contexts in `verify_proof` and gets their rent back
+    #[account(mut)]
+    pub proof_context_payer: UncheckedAccount<'info>,
+
+    /// Proof contexts recorded by `verify_proof`, used instead of the matching proof
+    /// instruction when supplied and closed once consumed
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = equality_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = equality_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub equality_proof_context: Option<Account<'info, ProofContext>>,
+
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = validity_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = validity_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub validity_proof_context: Option<Account<'info, ProofContext>>,
+
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = range_proof_context.authority == sender.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = range_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub range_proof_context: Option<Account<'info, ProofContext>>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<StealthTransfer>,
+    encrypted_amount_lo: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: [u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    stealth_pubkey: [u8; 32],
+    ephemeral_pubkey: [u8; 32],
+    view_tag: u8,
+    new_decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+    equality_proof_instruction_offset: i8,
+    validity_proof_instruction_offset: i8,
+    range_proof_instruction_offset: i8,
+) -> Result<()> {
+    ctx.accounts.veilpay_mint.assert_active()?;
+
+    // Nobody can prove knowledge of the stealth secret yet, so only check it is a usable key
+    require!(
+        validate_stealth_pubkey(&stealth_pubkey) && validate_stealth_pubkey(&ephemeral_pubkey),
+        VeilPayError::InvalidStealthPubkey
+    );
+
+    // 1. Debit the sender through their handles
+    let sender_amount_lo = grouped_ciphertext_view(&encrypted_amount_lo, SENDER_HANDLE_INDEX);
+    let sender_amount_hi = grouped_ciphertext_view(&encrypted_amount_hi, SENDER_HANDLE_INDEX);
+    let new_sender_balance = cspl_sub_with_lo_hi(
+        &ctx.accounts.sender_balance.encrypted_balance,
+        &sender_amount_lo,
+        &sender_amount_hi,
+    )?;
+
+    // 2. The recipient handles must be made for the stealth key and encrypt the same amount
+    let instructions_sysvar = ctx.accounts.instructions_sysvar.to_account_info();
+    let validity_proof = verify_proof::<
+        BatchedGroupedCiphertext3HandlesValidityProofData,
+        BatchedGroupedCiphertext3HandlesValidityProofContext,
+    >(
+        &instructions_sysvar,
+        validity_proof_instruction_offset,
+        ctx.accounts.validity_proof_context.as_deref(),
+    )?;
+    cspl_assert_valid_transfer(
+        &encrypted_amount_lo,
+        &encrypted_amount_hi,
+        &ctx.accounts.sender_balance.elgamal_pubkey,
+        &stealth_pubkey,
+        ctx.accounts.veilpay_mint.config.auditor_pubkey(),
+        &validity_proof,
+    )?;
+
+    // 3. Prove the remainder is non-negative and the amount halves are in range
+    let equality_proof = verify_proof::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        &instructions_sysvar,
+        equality_proof_instruction_offset,
+        ctx.accounts.equality_proof_context.as_deref(),
+    )?;
+    let range_proof = verify_proof::<
+        BatchedRangeProofU128Data,
+        BatchedRangeProofContext,
+    >(
+        &instructions_sysvar,
+        range_proof_instruction_offset,
+        ctx.accounts.range_proof_context.as_deref(),
+    )?;
+
+    cspl_assert_ge(
+        &new_sender_balance,
+        &sender_amount_lo,
+        &sender_amount_hi,
+        &ctx.accounts.sender_balance.elgamal_pubkey,
+        &equality_proof,
+        &range_proof,
+    )?;
+
+    let sender_balance = &mut ctx.accounts.sender_balance;
+    sender_balance.encrypted_balance = new_sender_balance;
+    sender_balance.decryptable_available_balance = new_decryptable_available_balance;
+    sender_balance.nonce += 1;
+
+    // 4. Open the stealth balance with the amount pending. It has no owner commitment, so
+    //    the only way to move the funds out is `sweep_stealth`.
+    let stealth_balance = &mut ctx.accounts.stealth_balance;
+    stealth_balance.mint = ctx.accounts.veilpay_mint.key();
+    stealth_balance.owner_commitment = [0u8; 32];
+    stealth_balance.elgamal_pubkey = stealth_pubkey;
+    stealth_balance.encrypted_balance = ZERO_CIPHERTEXT;
+    stealth_balance.decryptable_available_balance = [0u8; AE_CIPHERTEXT_SIZE];
+    stealth_balance.pending_balance_lo = ZERO_CIPHERTEXT;
+    stealth_balance.pending_balance_hi = ZERO_CIPHERTEXT;
+    stealth_balance.pending_balance_credit_counter = 0;
+    stealth_balance.open_escrow_count = 0;
+    stealth_balance.nonce = 0;
+    stealth_balance.payer = ctx.accounts.payer.key();
+    stealth_balance.bump = ctx.bumps.stealth_balance;
+    stealth_balance.credit_pending(
+        &grouped_ciphertext_view(&encrypted_amount_lo, RECIPIENT_HANDLE_INDEX),
+        &grouped_ciphertext_view(&encrypted_amount_hi, RECIPIENT_HANDLE_INDEX),
+    )?;
+
+    let clock = Clock::get()?;
+    emit!(StealthPaymentEvent {
+        stealth_pubkey,
+        ephemeral_pubkey,
+        view_tag,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
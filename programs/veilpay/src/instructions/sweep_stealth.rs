@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::VeilPayError;
+use crate::utils::{helpers::*, crypto::*, proof::*};
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    CiphertextCiphertextEqualityProofContext, CiphertextCiphertextEqualityProofData,
+};
+
+/// Remaining accounts are `(stealth_balance, stealth_balance_payer, proof_context)` triples.
+/// Each proof context is a ciphertext equality proof recorded by `verify_proof`, moving the
+/// stealth balance's total from the stealth key to the destination's ElGamal key. The stealth
+/// balance's rent goes back to whoever paid for it in `stealth_transfer`.
+#[derive(Accounts)]
+pub struct SweepStealth<'info> {
+    #[account(
+        mut,
+        seeds = [
+            BALANCE_SEED,
+            destination_balance.mint.as_ref(),
+            destination_balance.owner_commitment.as_ref(),
+        ],
+        bump = destination_balance.bump,
+        constraint = destination_balance.owner_commitment
+            == owner_commitment(&destination_balance.mint, &owner.key()) @ VeilPayError::UnauthorizedAccess
+    )]
+    pub destination_balance: Account<'info, ConfidentialBalance>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: Paid for the proof contexts in `verify_proof` and gets their rent back
+    #[account(mut)]
+    pub proof_context_payer: UncheckedAccount<'info>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SweepStealth<'info>>,
+    new_decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+) -> Result<()> {
+    require!(
+        !ctx.remaining_accounts.is_empty() && ctx.remaining_accounts.len() % 3 == 0,
+        VeilPayError::InvalidStealthSweepAccounts
+    );
+
+    let owner = ctx.accounts.owner.key();
+    let proof_context_payer = ctx.accounts.proof_context_payer.to_account_info();
+    let destination = &mut ctx.accounts.destination_balance;
+
+    for triple in ctx.remaining_accounts.chunks_exact(3) {
+        let stealth_balance = Account::<ConfidentialBalance>::try_from(&triple[0])?;
+        let stealth_balance_payer = &triple[1];
+        require_keys_eq!(stealth_balance.mint, destination.mint, VeilPayError::MintMismatch);
+        require!(
+            is_stealth_balance_pda(
+                triple[0].key,
+                &stealth_balance.mint,
+                &stealth_balance.elgamal_pubkey,
+                stealth_balance.bump,
+            ),
+            VeilPayError::InvalidStealthBalanceAccount
+        );
+
+        require_keys_eq!(
+            stealth_balance.payer,
+            stealth_balance_payer.key(),
+            VeilPayError::RentPayerMismatch
+        );
+
+        let proof_context = Account::<ProofContext>::try_from(&triple[2])?;
+        require_keys_eq!(proof_context.authority, owner, VeilPayError::UnauthorizedAccess);
+        require_keys_eq!(
+            proof_context.payer,
+            proof_context_payer.key(),
+            VeilPayError::RentPayerMismatch
+        );
+        let equality_proof = proof_context_data::<
+            CiphertextCiphertextEqualityProofData,
+            CiphertextCiphertextEqualityProofContext,
+        >(&proof_context)?;
+
+        // Everything the stealth balance holds, as one ciphertext under the stealth key. Only
+        // the holder of the stealth secret can prove it equal to one under the destination key.
+        let stealth_total = cspl_add_with_lo_hi(
+            &stealth_balance.encrypted_balance,
+            &stealth_balance.pending_balance_lo,
+            &stealth_balance.pending_balance_hi,
+        )?;
+        let swept = cspl_assert_ciphertext_eq(
+            &stealth_total,
+            &stealth_balance.elgamal_pubkey,
+            &destination.elgamal_pubkey,
+            &equality_proof,
+        )?;
+        destination.encrypted_balance = cspl_add(&destination.encrypted_balance, &swept)?;
+
+        // Closing here also rejects the same stealth balance or proof appearing twice
+        stealth_balance.close(stealth_balance_payer.clone())?;
+        proof_context.close(proof_context_payer.clone())?;
+    }
+
+    // Swept amounts go straight to the available balance, which the owner is signing for
+    destination.decryptable_available_balance = new_decryptable_available_balance;
+
+    Ok(())
+}
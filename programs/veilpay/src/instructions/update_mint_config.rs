@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::VeilPayError;
+
+#[derive(Accounts)]
+pub struct UpdateMintConfig<'info> {
+    #[account(
+        mut,
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump,
+        has_one = authority @ VeilPayError::UnauthorizedAccess
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<UpdateMintConfig>, config: MintConfig) -> Result<()> {
+    config.validate()?;
+
+    let mint = &mut ctx.accounts.veilpay_mint;
+    // Depositors accepted the fee when they deposited, so it is fixed for the mint's lifetime
+    require!(
+        config.has_same_fee(&mint.config),
+        VeilPayError::FeeConfigurationImmutable
+    );
+    mint.version = MINT_CONFIG_VERSION;
+    mint.config = config;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::VeilPayError;
+use crate::utils::stealth::validate_stealth_pubkey;
+
+#[derive(Accounts)]
+pub struct UpdateStealthMetaAddress<'info> {
+    #[account(
+        mut,
+        seeds = [STEALTH_META_ADDRESS_SEED, owner.key().as_ref()],
+        bump = stealth_meta_address.bump,
+        has_one = owner
+    )]
+    pub stealth_meta_address: Account<'info, StealthMetaAddress>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Rotate the published keys. Payments already announced stay spendable with the old keys.
+pub fn handler(
+    ctx: Context<UpdateStealthMetaAddress>,
+    view_pubkey: [u8; 32],
+    spend_pubkey: [u8; 32],
+) -> Result<()> {
+    require!(
+        validate_stealth_pubkey(&view_pubkey) && validate_stealth_pubkey(&spend_pubkey),
+        VeilPayError::InvalidStealthPubkey
+    );
+
+    let meta_address = &mut ctx.accounts.stealth_meta_address;
+    meta_address.view_pubkey = view_pubkey;
+    meta_address.spend_pubkey = spend_pubkey;
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::utils::proof::*;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+
+#[derive(Accounts)]
+#[instruction(context_id: u64)]
+pub struct VerifyProof<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ProofContext::LEN,
+        seeds = [
+            PROOF_CONTEXT_SEED,
+            authority.key().as_ref(),
+            &context_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub proof_context: Account<'info, ProofContext>,
+
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Instructions sysvar, read to locate the proof instruction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: Context state account written by the ZK ElGamal proof program, read instead of
+    /// the proof instruction when supplied; owner and authority are checked in the handler
+    pub zk_proof_context: Option<UncheckedAccount<'info>>,
+}
+
+pub fn handler(
+    ctx: Context<VerifyProof>,
+    _context_id: u64,
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    // The proof program verified the proof, either in this same transaction or earlier into its
+    // own context state account; we only record its context
+    let (proof_type, context) = match &ctx.accounts.zk_proof_context {
+        Some(zk_proof_context) => verify_proof_context_state(
+            &zk_proof_context.to_account_info(),
+            ctx.accounts.authority.key,
+        )?,
+        None => verify_any_proof_instruction(
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            proof_instruction_offset,
+        )?,
+    };
+
+    let proof_context = &mut ctx.accounts.proof_context;
+    proof_context.authority = ctx.accounts.authority.key();
+    proof_context.payer = ctx.accounts.payer.key();
+    proof_context.proof_type = proof_type as u8;
+    proof_context.context = context;
+    proof_context.bump = ctx.bumps.proof_context;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::VeilPayError;
+
+#[derive(Accounts)]
+pub struct VerifySolvency<'info> {
+    #[account(
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
+    /// CHECK: Lamport vault for native SOL or the mint's vault token account, the address is
+    /// checked in the handler since it depends on the underlying asset
+    pub vault: UncheckedAccount<'info>,
+}
+
+/// Permissionless check that the vault still backs everything deposited and not withdrawn.
+pub fn handler(ctx: Context<VerifySolvency>) -> Result<()> {
+    let veilpay_mint = &ctx.accounts.veilpay_mint;
+    let vault = &ctx.accounts.vault;
+
+    let vault_amount = if veilpay_mint.underlying_asset == Pubkey::default() {
+        let (vault_address, _) = Pubkey::find_program_address(&[VAULT_SEED], ctx.program_id);
+        require_keys_eq!(vault.key(), vault_address, VeilPayError::AccountNotFound);
+        vault.lamports()
+    } else {
+        let (vault_address, _) = Pubkey::find_program_address(
+            &[VAULT_SEED, veilpay_mint.key().as_ref()],
+            ctx.program_id,
+        );
+        require_keys_eq!(vault.key(), vault_address, VeilPayError::AccountNotFound);
+        TokenAccount::try_deserialize(&mut &vault.try_borrow_data()?[..])?.amount
+    };
+
+    require!(
+        vault_amount >= veilpay_mint.outstanding()?,
+        VeilPayError::VaultInsolvent
+    );
+
+    Ok(())
+}
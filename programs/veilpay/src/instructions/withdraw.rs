@@ -1,16 +1,33 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::constants::*;
-use crate::utils::{helpers::*, crypto::*};
-use anchor_lang::system_program;
+use crate::utils::{helpers::*, crypto::*, cpi::*, proof::*};
 use crate::errors::VeilPayError;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    BatchedRangeProofContext, BatchedRangeProofU64Data,
+    CiphertextCommitmentEqualityProofContext, CiphertextCommitmentEqualityProofData,
+};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(
         mut,
-        seeds = [BALANCE_SEED, signer.key().as_ref()],
-        bump = confidential_balance.bump
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
+    #[account(
+        mut,
+        seeds = [
+            BALANCE_SEED,
+            veilpay_mint.key().as_ref(),
+            confidential_balance.owner_commitment.as_ref(),
+        ],
+        bump = confidential_balance.bump,
+        constraint = confidential_balance.owner_commitment
+            == owner_commitment(&veilpay_mint.key(), &signer.key()) @ VeilPayError::UnauthorizedAccess
     )]
     pub confidential_balance: Account<'info, ConfidentialBalance>,
     
@@ -22,33 +39,101 @@ pub struct Withdraw<'info> {
     )]
     pub vault: AccountInfo<'info>,
     
-    #[account(mut)]
     pub signer: Signer<'info>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Only receives the withdrawn lamports, so the balance authority never has to
+    /// hold funds that would tie it to a wallet
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    /// CHECK: Only receives lamports, pinned to the mint's configured fee recipient
+    #[account(mut, address = veilpay_mint.config.fee_recipient)]
+    pub fee_recipient: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, read to locate the proof instructions in this transaction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: Paid for the supplied proof contexts in `verify_proof` and gets their rent back
+    #[account(mut)]
+    pub proof_context_payer: UncheckedAccount<'info>,
+
+    /// Proof contexts recorded by `verify_proof`, used instead of the matching proof
+    /// instruction when supplied and closed once consumed
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = equality_proof_context.authority == signer.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = equality_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub equality_proof_context: Option<Account<'info, ProofContext>>,
+
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = range_proof_context.authority == signer.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = range_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub range_proof_context: Option<Account<'info, ProofContext>>,
 }
 
 pub fn handler(
     ctx: Context<Withdraw>,
     amount: u64,
-    encrypted_amount: [u8; 64], // This should be Encrypted(-amount)
+    new_decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+    equality_proof_instruction_offset: i8,
+    range_proof_instruction_offset: i8,
 ) -> Result<()> {
-    // 1. ZK Proof Check (Missing in MVP)
-    // require!(verify_balance(encrypted_balance, amount), VeilPayError::InsufficientFunds);
-    
-    // 2. Transfer SOL from Vault to User
-    let rent = Rent::get()?.minimum_balance(0);
-    let vault_balance = ctx.accounts.vault.lamports();
-    
-    // SECURITY: Enforce balance check
-    // Extract decrypted balance from on-chain state
-    let decrypted_balance = cspl_decrypt(&ctx.accounts.confidential_balance.encrypted_balance);
-    
-    // Check if user has enough funds in their private balance
+    let veilpay_mint = &ctx.accounts.veilpay_mint;
+    veilpay_mint.assert_active()?;
+    // The lamport vault only backs the native SOL mint
+    require!(
+        veilpay_mint.underlying_asset == Pubkey::default(),
+        VeilPayError::UnsupportedUnderlyingAsset
+    );
     require!(
-        decrypted_balance >= amount,
-        VeilPayError::InsufficientBalance
+        amount <= veilpay_mint.config.max_withdraw_amount,
+        VeilPayError::TransactionLimitExceeded
     );
+    let fee = veilpay_mint
+        .config
+        .calculate_fee(amount)
+        .ok_or(VeilPayError::InvalidFeeConfiguration)?;
+
+    // 1. Subtract the public amount homomorphically and prove the remainder is non-negative
+    let new_balance =
+        cspl_sub_amount(&ctx.accounts.confidential_balance.encrypted_balance, amount)?;
 
+    let instructions_sysvar = ctx.accounts.instructions_sysvar.to_account_info();
+    let equality_proof = verify_proof::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        &instructions_sysvar,
+        equality_proof_instruction_offset,
+        ctx.accounts.equality_proof_context.as_deref(),
+    )?;
+    let range_proof = verify_proof::<
+        BatchedRangeProofU64Data,
+        BatchedRangeProofContext,
+    >(
+        &instructions_sysvar,
+        range_proof_instruction_offset,
+        ctx.accounts.range_proof_context.as_deref(),
+    )?;
+
+    cspl_assert_remaining_balance(
+        &new_balance,
+        &ctx.accounts.confidential_balance.elgamal_pubkey,
+        &equality_proof,
+        &range_proof,
+    )?;
+
+    // 2. Transfer SOL from Vault to the destination, less the withdrawal fee
+    let rent = Rent::get()?.minimum_balance(0);
+    let vault_balance = ctx.accounts.vault.lamports();
+    
     if vault_balance.saturating_sub(amount) < rent {
         return Err(ProgramError::InsufficientFunds.into());
     }
@@ -60,21 +145,28 @@ pub fn handler(
     ];
     let signer_seeds = &[&seeds[..]];
 
-    let cpi_context = CpiContext::new_with_signer(
-        ctx.accounts.system_program.to_account_info(),
-        system_program::Transfer {
-            from: ctx.accounts.vault.to_account_info(),
-            to: ctx.accounts.signer.to_account_info(),
-        },
+    transfer_lamports(
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.destination.to_account_info(),
+        amount - fee,
         signer_seeds,
-    );
-    system_program::transfer(cpi_context, amount)?;
+    )?;
+
+    if fee > 0 {
+        transfer_lamports(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.fee_recipient.to_account_info(),
+            fee,
+            signer_seeds,
+        )?;
+    }
+
+    ctx.accounts.veilpay_mint.record_withdrawal(amount)?;
 
-    // 3. Update Encrypted Balance (Subtract)
-    // Fix: Previously we were adding the encrypted amount, which caused the balance to increase on withdraw.
-    // For MVP, we decrypt, subtract, and re-encrypt.
-    let new_balance = decrypted_balance - amount;
-    ctx.accounts.confidential_balance.encrypted_balance = encrypt_amount(new_balance);
+    // 3. Update Encrypted Balance
+    ctx.accounts.confidential_balance.encrypted_balance = new_balance;
+    ctx.accounts.confidential_balance.decryptable_available_balance =
+        new_decryptable_available_balance;
         
     Ok(())
 }
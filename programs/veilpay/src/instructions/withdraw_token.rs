@@ -0,0 +1,184 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::constants::*;
+use crate::utils::{helpers::*, crypto::*, proof::*, token::*};
+use crate::errors::VeilPayError;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    BatchedRangeProofContext, BatchedRangeProofU64Data,
+    CiphertextCommitmentEqualityProofContext, CiphertextCommitmentEqualityProofData,
+};
+
+#[derive(Accounts)]
+pub struct WithdrawToken<'info> {
+    #[account(
+        mut,
+        seeds = [MINT_SEED, veilpay_mint.underlying_asset.as_ref()],
+        bump = veilpay_mint.bump
+    )]
+    pub veilpay_mint: Account<'info, VeilPayMint>,
+
+    #[account(
+        mut,
+        seeds = [
+            BALANCE_SEED,
+            veilpay_mint.key().as_ref(),
+            confidential_balance.owner_commitment.as_ref(),
+        ],
+        bump = confidential_balance.bump,
+        constraint = confidential_balance.owner_commitment
+            == owner_commitment(&veilpay_mint.key(), &signer.key()) @ VeilPayError::UnauthorizedAccess
+    )]
+    pub confidential_balance: Account<'info, ConfidentialBalance>,
+
+    #[account(
+        address = veilpay_mint.underlying_asset,
+        mint::token_program = token_program
+    )]
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, veilpay_mint.key().as_ref()],
+        bump
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = underlying_mint,
+        token::token_program = token_program
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = underlying_mint,
+        token::authority = veilpay_mint.config.fee_recipient,
+        token::token_program = token_program
+    )]
+    pub fee_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub signer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Instructions sysvar, read to locate the proof instructions in this transaction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: Paid for the supplied proof contexts in `verify_proof` and gets their rent back
+    #[account(mut)]
+    pub proof_context_payer: UncheckedAccount<'info>,
+
+    /// Proof contexts recorded by `verify_proof`, used instead of the matching proof
+    /// instruction when supplied and closed once consumed
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = equality_proof_context.authority == signer.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = equality_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub equality_proof_context: Option<Account<'info, ProofContext>>,
+
+    #[account(
+        mut,
+        close = proof_context_payer,
+        constraint = range_proof_context.authority == signer.key() @ VeilPayError::UnauthorizedAccess,
+        constraint = range_proof_context.payer == proof_context_payer.key() @ VeilPayError::RentPayerMismatch
+    )]
+    pub range_proof_context: Option<Account<'info, ProofContext>>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, WithdrawToken<'info>>,
+    amount: u64,
+    new_decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+    equality_proof_instruction_offset: i8,
+    range_proof_instruction_offset: i8,
+) -> Result<()> {
+    let veilpay_mint = &ctx.accounts.veilpay_mint;
+    veilpay_mint.assert_active()?;
+    require!(
+        amount <= veilpay_mint.config.max_withdraw_amount,
+        VeilPayError::TransactionLimitExceeded
+    );
+    let fee = veilpay_mint
+        .config
+        .calculate_fee(amount)
+        .ok_or(VeilPayError::InvalidFeeConfiguration)?;
+
+    // 1. Subtract the public amount homomorphically and prove the remainder is non-negative
+    let new_balance =
+        cspl_sub_amount(&ctx.accounts.confidential_balance.encrypted_balance, amount)?;
+
+    let instructions_sysvar = ctx.accounts.instructions_sysvar.to_account_info();
+    let equality_proof = verify_proof::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        &instructions_sysvar,
+        equality_proof_instruction_offset,
+        ctx.accounts.equality_proof_context.as_deref(),
+    )?;
+    let range_proof = verify_proof::<
+        BatchedRangeProofU64Data,
+        BatchedRangeProofContext,
+    >(
+        &instructions_sysvar,
+        range_proof_instruction_offset,
+        ctx.accounts.range_proof_context.as_deref(),
+    )?;
+
+    cspl_assert_remaining_balance(
+        &new_balance,
+        &ctx.accounts.confidential_balance.elgamal_pubkey,
+        &equality_proof,
+        &range_proof,
+    )?;
+
+    // 2. Transfer tokens from the vault, less the withdrawal fee; the mint PDA signs and any
+    // transfer-hook extra accounts come in as remaining accounts
+    let seeds = &[
+        MINT_SEED,
+        veilpay_mint.underlying_asset.as_ref(),
+        &[veilpay_mint.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    let decimals = ctx.accounts.underlying_mint.decimals;
+
+    transfer_checked_with_hook(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.underlying_mint.to_account_info(),
+        &ctx.accounts.destination_token_account.to_account_info(),
+        &veilpay_mint.to_account_info(),
+        ctx.remaining_accounts,
+        amount - fee,
+        decimals,
+        signer_seeds,
+    )?;
+
+    if fee > 0 {
+        transfer_checked_with_hook(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.underlying_mint.to_account_info(),
+            &ctx.accounts.fee_token_account.to_account_info(),
+            &veilpay_mint.to_account_info(),
+            ctx.remaining_accounts,
+            fee,
+            decimals,
+            signer_seeds,
+        )?;
+    }
+
+    ctx.accounts.veilpay_mint.record_withdrawal(amount)?;
+
+    // 3. Update Encrypted Balance
+    ctx.accounts.confidential_balance.encrypted_balance = new_balance;
+    ctx.accounts.confidential_balance.decryptable_available_balance =
+        new_decryptable_available_balance;
+
+    Ok(())
+}
@@ -10,6 +10,7 @@ pub mod errors;
 declare_id!("5vKU63aqbKn5F4NWMnaMoq1jjSVSR8DNCFbfJnc4fPUZ");
 
 use instructions::*;
+use state::MintConfig;
 
 #[program]
 pub mod veilpay {
@@ -17,30 +18,60 @@ pub mod veilpay {
 
     pub fn initialize_mint(
         ctx: Context<InitializeMint>,
-        cspl_config: [u8; 64],
+        underlying_asset: Pubkey,
+        config: MintConfig,
+        genesis_hash: [u8; 32],
     ) -> Result<()> {
-        instructions::initialize_mint::handler(ctx, cspl_config)
+        instructions::initialize_mint::handler(ctx, underlying_asset, config, genesis_hash)
+    }
+
+    pub fn update_mint_config(
+        ctx: Context<UpdateMintConfig>,
+        config: MintConfig,
+    ) -> Result<()> {
+        instructions::update_mint_config::handler(ctx, config)
     }
 
     pub fn init_balance(
         ctx: Context<InitBalance>,
+        elgamal_pubkey: [u8; 32],
+        decryptable_zero_balance: [u8; 36],
+        pubkey_validity_proof_instruction_offset: i8,
     ) -> Result<()> {
-        instructions::init_balance::handler(ctx)
+        instructions::init_balance::handler(
+            ctx,
+            elgamal_pubkey,
+            decryptable_zero_balance,
+            pubkey_validity_proof_instruction_offset,
+        )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn private_transfer(
         ctx: Context<PrivateTransfer>,
-        encrypted_amount: [u8; 64],
+        encrypted_amount_lo: [u8; 128],
+        encrypted_amount_hi: [u8; 128],
+        recipient_commitment: [u8; 32],
         expected_nonce: u64,
         commitment_hash: [u8; 32],
         encrypted_tag: [u8; 32],
+        new_decryptable_available_balance: [u8; 36],
+        equality_proof_instruction_offset: i8,
+        validity_proof_instruction_offset: i8,
+        range_proof_instruction_offset: i8,
     ) -> Result<()> {
         instructions::private_transfer::handler(
             ctx,
-            encrypted_amount,
+            encrypted_amount_lo,
+            encrypted_amount_hi,
+            recipient_commitment,
             expected_nonce,
             commitment_hash,
             encrypted_tag,
+            new_decryptable_available_balance,
+            equality_proof_instruction_offset,
+            validity_proof_instruction_offset,
+            range_proof_instruction_offset,
         )
     }
 
@@ -48,25 +79,95 @@ pub mod veilpay {
         ctx: Context<Deposit>,
         amount: u64,
         encrypted_amount: [u8; 64],
+        equality_proof_instruction_offset: i8,
     ) -> Result<()> {
-        instructions::deposit::handler(ctx, amount, encrypted_amount)
+        instructions::deposit::handler(
+            ctx,
+            amount,
+            encrypted_amount,
+            equality_proof_instruction_offset,
+        )
     }
 
     pub fn withdraw(
         ctx: Context<Withdraw>,
         amount: u64,
+        new_decryptable_available_balance: [u8; 36],
+        equality_proof_instruction_offset: i8,
+        range_proof_instruction_offset: i8,
+    ) -> Result<()> {
+        instructions::withdraw::handler(
+            ctx,
+            amount,
+            new_decryptable_available_balance,
+            equality_proof_instruction_offset,
+            range_proof_instruction_offset,
+        )
+    }
+
+    pub fn initialize_vault(
+        ctx: Context<InitializeVault>,
+    ) -> Result<()> {
+        instructions::initialize_vault::handler(ctx)
+    }
+
+    pub fn deposit_token<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositToken<'info>>,
+        amount: u64,
         encrypted_amount: [u8; 64],
+        equality_proof_instruction_offset: i8,
     ) -> Result<()> {
-        instructions::withdraw::handler(ctx, amount, encrypted_amount)
+        instructions::deposit_token::handler(
+            ctx,
+            amount,
+            encrypted_amount,
+            equality_proof_instruction_offset,
+        )
     }
 
+    pub fn withdraw_token<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawToken<'info>>,
+        amount: u64,
+        new_decryptable_available_balance: [u8; 36],
+        equality_proof_instruction_offset: i8,
+        range_proof_instruction_offset: i8,
+    ) -> Result<()> {
+        instructions::withdraw_token::handler(
+            ctx,
+            amount,
+            new_decryptable_available_balance,
+            equality_proof_instruction_offset,
+            range_proof_instruction_offset,
+        )
+    }
+
+    pub fn verify_solvency(
+        ctx: Context<VerifySolvency>,
+    ) -> Result<()> {
+        instructions::verify_solvency::handler(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_transfer(
         ctx: Context<CreateTransfer>,
-        amount: u64,
-        encrypted_amount: [u8; 64],
-        recipient: Pubkey,
+        encrypted_amount_lo: [u8; 128],
+        encrypted_amount_hi: [u8; 128],
+        recipient_commitment: [u8; 32],
+        new_decryptable_available_balance: [u8; 36],
+        equality_proof_instruction_offset: i8,
+        validity_proof_instruction_offset: i8,
+        range_proof_instruction_offset: i8,
     ) -> Result<()> {
-        instructions::create_transfer::handler(ctx, amount, encrypted_amount, recipient)
+        instructions::create_transfer::handler(
+            ctx,
+            encrypted_amount_lo,
+            encrypted_amount_hi,
+            recipient_commitment,
+            new_decryptable_available_balance,
+            equality_proof_instruction_offset,
+            validity_proof_instruction_offset,
+            range_proof_instruction_offset,
+        )
     }
 
     pub fn claim_transfer(
@@ -77,13 +178,114 @@ pub mod veilpay {
 
     pub fn cancel_transfer(
         ctx: Context<CancelTransfer>,
+        new_decryptable_available_balance: [u8; 36],
     ) -> Result<()> {
-        instructions::cancel_transfer::handler(ctx)
+        instructions::cancel_transfer::handler(ctx, new_decryptable_available_balance)
     }
 
+    pub fn close_balance(
+        ctx: Context<CloseBalance>,
+        zero_proof_instruction_offset: i8,
+    ) -> Result<()> {
+        instructions::close_balance::handler(ctx, zero_proof_instruction_offset)
+    }
+
+    /// Debug builds only: wipes a balance without moving any funds out of the vault
+    #[cfg(feature = "debug")]
     pub fn reset_account(
         ctx: Context<ResetAccount>,
+        decryptable_zero_balance: [u8; 36],
+    ) -> Result<()> {
+        instructions::reset_account::handler(ctx, decryptable_zero_balance)
+    }
+
+    pub fn apply_pending_balance(
+        ctx: Context<ApplyPendingBalance>,
+        expected_pending_balance_credit_counter: u64,
+        new_decryptable_available_balance: [u8; 36],
+    ) -> Result<()> {
+        instructions::apply_pending_balance::handler(
+            ctx,
+            expected_pending_balance_credit_counter,
+            new_decryptable_available_balance,
+        )
+    }
+
+    pub fn verify_proof(
+        ctx: Context<VerifyProof>,
+        context_id: u64,
+        proof_instruction_offset: i8,
+    ) -> Result<()> {
+        instructions::verify_proof::handler(ctx, context_id, proof_instruction_offset)
+    }
+
+    pub fn close_proof_context(
+        ctx: Context<CloseProofContext>,
+    ) -> Result<()> {
+        instructions::close_proof_context::handler(ctx)
+    }
+
+    pub fn register_stealth_meta_address(
+        ctx: Context<RegisterStealthMetaAddress>,
+        view_pubkey: [u8; 32],
+        spend_pubkey: [u8; 32],
+    ) -> Result<()> {
+        instructions::register_stealth_meta_address::handler(ctx, view_pubkey, spend_pubkey)
+    }
+
+    pub fn update_stealth_meta_address(
+        ctx: Context<UpdateStealthMetaAddress>,
+        view_pubkey: [u8; 32],
+        spend_pubkey: [u8; 32],
+    ) -> Result<()> {
+        instructions::update_stealth_meta_address::handler(ctx, view_pubkey, spend_pubkey)
+    }
+
+    pub fn announce_stealth_payment(
+        ctx: Context<AnnounceStealthPayment>,
+        stealth_pubkey: [u8; 32],
+        ephemeral_pubkey: [u8; 32],
+        view_tag: u8,
+    ) -> Result<()> {
+        instructions::announce_stealth_payment::handler(
+            ctx,
+            stealth_pubkey,
+            ephemeral_pubkey,
+            view_tag,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn stealth_transfer(
+        ctx: Context<StealthTransfer>,
+        encrypted_amount_lo: [u8; 128],
+        encrypted_amount_hi: [u8; 128],
+        stealth_pubkey: [u8; 32],
+        ephemeral_pubkey: [u8; 32],
+        view_tag: u8,
+        new_decryptable_available_balance: [u8; 36],
+        equality_proof_instruction_offset: i8,
+        validity_proof_instruction_offset: i8,
+        range_proof_instruction_offset: i8,
+    ) -> Result<()> {
+        instructions::stealth_transfer::handler(
+            ctx,
+            encrypted_amount_lo,
+            encrypted_amount_hi,
+            stealth_pubkey,
+            ephemeral_pubkey,
+            view_tag,
+            new_decryptable_available_balance,
+            equality_proof_instruction_offset,
+            validity_proof_instruction_offset,
+            range_proof_instruction_offset,
+        )
+    }
+
+    pub fn sweep_stealth<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SweepStealth<'info>>,
+        new_decryptable_available_balance: [u8; 36],
     ) -> Result<()> {
-        instructions::reset_account::handler(ctx)
+        instructions::sweep_stealth::handler(ctx, new_decryptable_available_balance)
     }
 }
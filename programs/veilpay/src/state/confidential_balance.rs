@@ -1,13 +1,73 @@
 use anchor_lang::prelude::*;
+use crate::constants::{
+    AE_CIPHERTEXT_SIZE, ELGAMAL_PUBKEY_SIZE, MAX_PENDING_BALANCE_CREDITS,
+    TRANSFER_AMOUNT_LO_BIT_LENGTH,
+};
+use crate::errors::VeilPayError;
+use crate::utils::crypto::*;
 
 #[account]
 pub struct ConfidentialBalance {
-    pub owner_commitment: [u8; 32], // hashed owner pubkey
-    pub encrypted_balance: [u8; 64], // Arcium encrypted value
+    pub mint: Pubkey, // VeilPayMint this balance is denominated in
+    pub owner_commitment: [u8; 32], // blinded commitment to the balance authority, also the PDA seed; zero for stealth balances
+    pub elgamal_pubkey: [u8; ELGAMAL_PUBKEY_SIZE], // owner's registered ElGamal public key, or the one-time stealth key
+    pub encrypted_balance: [u8; 64], // available balance, twisted ElGamal ciphertext (commitment || handle)
+    // Same balance under the owner's symmetric AE key, so wallets can read it without a
+    // discrete log. The program cannot check it; only the owner keeps it in sync.
+    pub decryptable_available_balance: [u8; AE_CIPHERTEXT_SIZE],
+    // Incoming credits land here so they never invalidate proofs made against the available
+    // balance. Kept as lo/hi halves so the owner can decode them (32 and 48 bits at most).
+    pub pending_balance_lo: [u8; 64],
+    pub pending_balance_hi: [u8; 64],
+    pub pending_balance_credit_counter: u64,
+    pub open_escrow_count: u64, // create_transfer escrows from this balance not yet claimed or cancelled
     pub nonce: u64,
+    pub payer: Pubkey, // funded the account and gets its rent back on close
     pub bump: u8,
 }
 
 impl ConfidentialBalance {
-    pub const LEN: usize = 32 + 64 + 8 + 1; // owner_commitment + encrypted_balance + nonce + bump
-}
\ No newline at end of file
+    pub const LEN: usize = 32 + 32 + ELGAMAL_PUBKEY_SIZE + 64 + AE_CIPHERTEXT_SIZE + 64 + 64 + 8 + 8 + 8 + 32 + 1; // mint + owner_commitment + elgamal_pubkey + encrypted_balance + decryptable_available_balance + pending_balance_lo + pending_balance_hi + pending_balance_credit_counter + open_escrow_count + nonce + payer + bump
+
+    /// Add an incoming lo/hi amount to the pending balance.
+    pub fn credit_pending(&mut self, amount_lo: &[u8; 64], amount_hi: &[u8; 64]) -> Result<()> {
+        require!(
+            self.pending_balance_credit_counter < MAX_PENDING_BALANCE_CREDITS,
+            VeilPayError::MaxPendingBalanceCreditsExceeded
+        );
+
+        self.pending_balance_lo = cspl_add(&self.pending_balance_lo, amount_lo)?;
+        self.pending_balance_hi = cspl_add(&self.pending_balance_hi, amount_hi)?;
+        self.pending_balance_credit_counter += 1;
+
+        Ok(())
+    }
+
+    /// Record an escrow opened by `create_transfer` from this balance.
+    pub fn open_escrow(&mut self) -> Result<()> {
+        self.open_escrow_count = self
+            .open_escrow_count
+            .checked_add(1)
+            .ok_or(VeilPayError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Record that one of this balance's escrows was claimed or cancelled.
+    pub fn close_escrow(&mut self) -> Result<()> {
+        self.open_escrow_count = self
+            .open_escrow_count
+            .checked_sub(1)
+            .ok_or(VeilPayError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Credit a verified deposit of `amount`, split so that the lo half keeps the owner's
+    /// handle and the public hi part carries a zero opening.
+    pub fn credit_deposit(&mut self, amount: u64, encrypted_amount: &[u8; 64]) -> Result<()> {
+        let (_, amount_hi) = split_amount(amount);
+        let deposit_lo =
+            cspl_sub_amount(encrypted_amount, amount_hi << TRANSFER_AMOUNT_LO_BIT_LENGTH)?;
+        let deposit_hi = cspl_add_amount(&ZERO_CIPHERTEXT, amount_hi)?;
+        self.credit_pending(&deposit_lo, &deposit_hi)
+    }
+}
@@ -1,7 +1,11 @@
 pub mod veilpay_mint;
 pub mod confidential_balance;
 pub mod pending_transfer;
+pub mod proof_context;
+pub mod stealth_meta_address;
 
 pub use veilpay_mint::*;
 pub use confidential_balance::*;
-pub use pending_transfer::*;
\ No newline at end of file
+pub use pending_transfer::*;
+pub use proof_context::*;
+pub use stealth_meta_address::*;
\ No newline at end of file
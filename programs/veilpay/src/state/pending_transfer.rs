@@ -1,22 +1,30 @@
 use anchor_lang::prelude::*;
-use crate::constants::ENCRYPTED_VALUE_SIZE;
+use crate::constants::{ELGAMAL_PUBKEY_SIZE, GROUPED_ENCRYPTED_VALUE_SIZE};
 
 #[account]
 pub struct PendingTransfer {
-    pub sender: Pubkey,              // 32
-    pub recipient: Pubkey,           // 32
-    pub amount: u64,                 // 8
-    pub encrypted_amount: [u8; ENCRYPTED_VALUE_SIZE], // 64
+    pub mint: Pubkey,                // 32
+    pub sender_commitment: [u8; 32], // 32, sender's owner commitment
+    pub recipient_commitment: [u8; 32], // 32, recipient's owner commitment
+    pub recipient_elgamal_pubkey: [u8; ELGAMAL_PUBKEY_SIZE], // 32, key the recipient handles were made for
+    pub encrypted_amount_lo: [u8; GROUPED_ENCRYPTED_VALUE_SIZE], // 128, sender/recipient/auditor handles
+    pub encrypted_amount_hi: [u8; GROUPED_ENCRYPTED_VALUE_SIZE], // 128
     pub timestamp: i64,              // 8
+    pub nonce: u64,                  // 8, sender nonce the PDA was derived from
+    pub payer: Pubkey,               // 32, funded the account and gets its rent back on close
     pub bump: u8,                    // 1
 }
 
 impl PendingTransfer {
     pub const LEN: usize = 8 + // discriminator
-        32 + // sender
-        32 + // recipient
-        8 + // amount
-        ENCRYPTED_VALUE_SIZE + // encrypted_amount
+        32 + // mint
+        32 + // sender_commitment
+        32 + // recipient_commitment
+        ELGAMAL_PUBKEY_SIZE + // recipient_elgamal_pubkey
+        GROUPED_ENCRYPTED_VALUE_SIZE + // encrypted_amount_lo
+        GROUPED_ENCRYPTED_VALUE_SIZE + // encrypted_amount_hi
         8 + // timestamp
+        8 + // nonce
+        32 + // payer
         1; // bump
 }
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+use crate::constants::MAX_PROOF_CONTEXT_SIZE;
+
+/// A proof statement verified by the ZK ElGamal proof program in an earlier transaction, kept
+/// so that instructions whose proofs do not fit in one transaction can consume it later.
+#[account]
+pub struct ProofContext {
+    pub authority: Pubkey,  // 32, only they can consume or close it
+    pub payer: Pubkey,      // 32, funded the account and gets its rent back on close
+    pub proof_type: u8,     // 1, zk-sdk ProofType
+    pub context: Vec<u8>,   // 4 + up to MAX_PROOF_CONTEXT_SIZE, the verified proof context
+    pub bump: u8,           // 1
+}
+
+impl ProofContext {
+    pub const LEN: usize = 32 + 32 + 1 + 4 + MAX_PROOF_CONTEXT_SIZE + 1;
+}
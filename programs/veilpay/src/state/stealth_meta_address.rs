@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+/// Keys a recipient publishes so senders can derive one-time stealth keys for them.
+#[account]
+pub struct StealthMetaAddress {
+    pub owner: Pubkey,          // 32, the identity senders look the meta-address up by
+    pub view_pubkey: [u8; 32],  // 32, v * G, used to reach the shared secret
+    pub spend_pubkey: [u8; 32], // 32, ElGamal key every stealth key is derived from
+    pub bump: u8,               // 1
+}
+
+impl StealthMetaAddress {
+    pub const LEN: usize = 32 + 32 + 32 + 1;
+}
@@ -1,12 +1,101 @@
 use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::VeilPayError;
+
+/// Settings chosen when the mint is created. The mint authority can change everything but the
+/// fee afterwards.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MintConfig {
+    // Compliance key that every transfer amount must also be encrypted under (all zero = none)
+    pub auditor_elgamal_pubkey: [u8; ELGAMAL_PUBKEY_SIZE],
+    pub max_deposit_amount: u64,
+    pub max_withdraw_amount: u64,
+    // Withdrawal fee, capped at maximum_fee and paid out to fee_recipient
+    pub fee_basis_points: u16,
+    pub maximum_fee: u64,
+    pub fee_recipient: Pubkey,
+    pub paused: bool,
+}
+
+impl MintConfig {
+    pub const LEN: usize = ELGAMAL_PUBKEY_SIZE + 8 + 8 + 2 + 8 + 32 + 1; // auditor_elgamal_pubkey + max_deposit_amount + max_withdraw_amount + fee_basis_points + maximum_fee + fee_recipient + paused
+
+    /// The auditor's ElGamal public key, if one is configured.
+    pub fn auditor_pubkey(&self) -> Option<&[u8; ELGAMAL_PUBKEY_SIZE]> {
+        (self.auditor_elgamal_pubkey != [0u8; ELGAMAL_PUBKEY_SIZE])
+            .then_some(&self.auditor_elgamal_pubkey)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.fee_basis_points <= MAX_FEE_BASIS_POINTS,
+            VeilPayError::InvalidFeeConfiguration
+        );
+        Ok(())
+    }
+
+    /// Whether `other` charges the same withdrawal fee to the same recipient.
+    pub fn has_same_fee(&self, other: &MintConfig) -> bool {
+        self.fee_basis_points == other.fee_basis_points
+            && self.maximum_fee == other.maximum_fee
+            && self.fee_recipient == other.fee_recipient
+    }
+
+    /// Fee charged on a withdrawal of `amount`, rounded up.
+    pub fn calculate_fee(&self, amount: u64) -> Option<u64> {
+        let fee = (amount as u128 * self.fee_basis_points as u128)
+            .div_ceil(MAX_FEE_BASIS_POINTS as u128);
+        Some(u64::try_from(fee).ok()?.min(self.maximum_fee))
+    }
+}
 
 #[account]
 pub struct VeilPayMint {
+    pub version: u8,
     pub authority : Pubkey,
-    pub cspl_config: [u8; 64], // Arcium confidential params
+    pub underlying_asset: Pubkey, // Pubkey::default() for native SOL
+    pub config: MintConfig,
+    // Running totals moved through the vault, checked by verify_solvency
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    // Genesis hash of the cluster the mint was created on, binds commitment hashes to it
+    pub genesis_hash: [u8; 32],
     pub bump: u8,
 }
 
 impl VeilPayMint {
-    pub const LEN: usize = 32 + 64 + 1; // Pubkey + cspl_config + bump
-}
\ No newline at end of file
+    pub const LEN: usize = 1 + 32 + 32 + MintConfig::LEN + 8 + 8 + 32 + 1; // version + authority + underlying_asset + config + total_deposited + total_withdrawn + genesis_hash + bump
+
+    /// Reject mints written by another layout version or paused by the authority.
+    pub fn assert_active(&self) -> Result<()> {
+        require!(
+            self.version == MINT_CONFIG_VERSION,
+            VeilPayError::UnsupportedMintVersion
+        );
+        require!(!self.config.paused, VeilPayError::MintPaused);
+        Ok(())
+    }
+
+    pub fn record_deposit(&mut self, amount: u64) -> Result<()> {
+        self.total_deposited = self
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(VeilPayError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    pub fn record_withdrawal(&mut self, amount: u64) -> Result<()> {
+        self.total_withdrawn = self
+            .total_withdrawn
+            .checked_add(amount)
+            .ok_or(VeilPayError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Amount the vault must still hold to back every balance and pending transfer.
+    pub fn outstanding(&self) -> Result<u64> {
+        self.total_deposited
+            .checked_sub(self.total_withdrawn)
+            .ok_or(VeilPayError::ArithmeticOverflow.into())
+    }
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use solana_program::program::invoke_signed;
+use solana_system_interface::instruction as system_instruction;
+
+/// Move `amount` lamports from the system account `from` to `to`. PDAs sign with `signer_seeds`.
+///
+/// Anchor's `system_program::transfer` invokes through a CPI that panics outside the SBF
+/// runtime. solana-program's `invoke_signed` is the same syscall on-chain, and off-chain it goes
+/// through the syscall stubs that `solana-program-test` installs, so natively processed tests
+/// run the transfer too.
+pub fn transfer_lamports<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_signed(
+        &system_instruction::transfer(from.key, to.key, amount),
+        &[from.clone(), to.clone()],
+        signer_seeds,
+    )?;
+    Ok(())
+}
@@ -1,131 +1,379 @@
 use anchor_lang::prelude::*;
 use solana_keccak_hasher::hashv;
+use solana_curve25519::ristretto::{
+    add_ristretto, multiply_ristretto, subtract_ristretto, PodRistrettoPoint,
+};
+use solana_curve25519::scalar::PodScalar;
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    BatchedGroupedCiphertext3HandlesValidityProofContext, BatchedRangeProofContext,
+    CiphertextCiphertextEqualityProofContext, CiphertextCommitmentEqualityProofContext, PubkeyValidityProofContext,
+    ZeroCiphertextProofContext,
+};
 use crate::constants::*;
+use crate::errors::VeilPayError;
 
+/// Compressed Ristretto255 basepoint `G`, the generator amounts are committed under.
+///
+/// Ciphertexts are twisted ElGamal: `commitment = amount * G + opening * H` followed by
+/// `handle = opening * P` for the owner's ElGamal public key `P`.
+const RISTRETTO_BASEPOINT: PodRistrettoPoint = PodRistrettoPoint([
+    0xe2, 0xf2, 0xae, 0x0a, 0x6a, 0xbc, 0x4e, 0x71, 0xa8, 0x84, 0xa9, 0x61, 0xc5, 0x00, 0x51, 0x5f,
+    0x58, 0xe3, 0x0b, 0x6a, 0xa5, 0x82, 0xdd, 0x8d, 0xb6, 0xa6, 0x59, 0x45, 0xe0, 0x8d, 0x2d, 0x76,
+]);
+
+/// Encryption of zero with a zero opening (both halves are the identity point).
+pub const ZERO_CIPHERTEXT: [u8; ENCRYPTED_VALUE_SIZE] = [0u8; ENCRYPTED_VALUE_SIZE];
+
+/// Check that a debit leaves the balance non-negative, without decrypting anything.
+///
+/// `new_balance` is the balance ciphertext after subtracting the lo/hi transfer amount. The
+/// equality proof binds it to a fresh Pedersen commitment whose opening the sender knows, and
+/// the range proof then shows that commitment is in `[0, 2^64)` and the amount halves are within
+/// their 16 and 32 bits.
 pub fn cspl_assert_ge(
-    balance: &[u8; ENCRYPTED_VALUE_SIZE],
-    amount: &[u8; ENCRYPTED_VALUE_SIZE],
+    new_balance: &[u8; ENCRYPTED_VALUE_SIZE],
+    amount_lo: &[u8; ENCRYPTED_VALUE_SIZE],
+    amount_hi: &[u8; ENCRYPTED_VALUE_SIZE],
+    elgamal_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    equality_proof: &CiphertextCommitmentEqualityProofContext,
+    range_proof: &BatchedRangeProofContext,
+) -> Result<()> {
+    require!(
+        bytemuck::bytes_of(&equality_proof.pubkey) == elgamal_pubkey.as_ref()
+            && bytemuck::bytes_of(&equality_proof.ciphertext) == new_balance.as_ref(),
+        VeilPayError::EqualityProofVerificationFailed
+    );
+
+    require!(
+        range_proof.commitments[0] == equality_proof.commitment
+            && bytemuck::bytes_of(&range_proof.commitments[1]) == &amount_lo[..ELGAMAL_C1_SIZE]
+            && bytemuck::bytes_of(&range_proof.commitments[2]) == &amount_hi[..ELGAMAL_C1_SIZE]
+            && range_proof.bit_lengths[0] == BALANCE_BIT_LENGTH
+            && range_proof.bit_lengths[1] == TRANSFER_AMOUNT_LO_BIT_LENGTH
+            && range_proof.bit_lengths[2] == TRANSFER_AMOUNT_HI_BIT_LENGTH
+            && range_proof.bit_lengths[3] == RANGE_PROOF_PADDING_BIT_LENGTH,
+        VeilPayError::RangeProofVerificationFailed
+    );
+
+    Ok(())
+}
+
+/// Check that a balance debited by a public amount is still non-negative.
+///
+/// Same equality-then-range construction as [`cspl_assert_ge`], with a single 64-bit range
+/// proof since the debited amount is already public.
+pub fn cspl_assert_remaining_balance(
+    new_balance: &[u8; ENCRYPTED_VALUE_SIZE],
+    elgamal_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    equality_proof: &CiphertextCommitmentEqualityProofContext,
+    range_proof: &BatchedRangeProofContext,
 ) -> Result<()> {
-    // MOCK: Extract u64 directly from first 8 bytes
-    let balance_val = extract_encrypted_value(balance);
-    let amount_val = extract_encrypted_value(amount);
+    require!(
+        bytemuck::bytes_of(&equality_proof.pubkey) == elgamal_pubkey.as_ref()
+            && bytemuck::bytes_of(&equality_proof.ciphertext) == new_balance.as_ref(),
+        VeilPayError::EqualityProofVerificationFailed
+    );
 
     require!(
-        balance_val >= amount_val,
-        ErrorCode::InsufficientBalance
+        range_proof.commitments[0] == equality_proof.commitment
+            && range_proof.bit_lengths[0] == BALANCE_BIT_LENGTH,
+        VeilPayError::RangeProofVerificationFailed
     );
 
     Ok(())
 }
 
+/// Check that `ciphertext` encrypts exactly the public `amount` under `elgamal_pubkey`.
+///
+/// The equality proof must be against the commitment `amount * G` with a zero opening, which
+/// the program recomputes itself so the prover cannot substitute a different value.
+pub fn cspl_assert_eq_amount(
+    ciphertext: &[u8; ENCRYPTED_VALUE_SIZE],
+    amount: u64,
+    elgamal_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    equality_proof: &CiphertextCommitmentEqualityProofContext,
+) -> Result<()> {
+    let expected_commitment = amount_to_point(amount)?;
+
+    require!(
+        bytemuck::bytes_of(&equality_proof.pubkey) == elgamal_pubkey.as_ref()
+            && bytemuck::bytes_of(&equality_proof.ciphertext) == ciphertext.as_ref()
+            && bytemuck::bytes_of(&equality_proof.commitment) == expected_commitment.0.as_ref(),
+        VeilPayError::EqualityProofVerificationFailed
+    );
+
+    Ok(())
+}
+
+/// Check that the zero ciphertext proof shows `ciphertext` encrypts zero under the owner's key.
+pub fn cspl_assert_zero(
+    ciphertext: &[u8; ENCRYPTED_VALUE_SIZE],
+    elgamal_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    zero_proof: &ZeroCiphertextProofContext,
+) -> Result<()> {
+    require!(
+        bytemuck::bytes_of(&zero_proof.pubkey) == elgamal_pubkey.as_ref()
+            && bytemuck::bytes_of(&zero_proof.ciphertext) == ciphertext.as_ref(),
+        VeilPayError::ZeroBalanceProofVerificationFailed
+    );
+
+    Ok(())
+}
+
+/// Check that the ciphertext equality proof moves `source` under `source_pubkey` to a
+/// ciphertext of the same amount under `destination_pubkey`, and return that ciphertext.
+///
+/// The proof can only be made with the source secret key, so it also shows the prover
+/// controls the source.
+pub fn cspl_assert_ciphertext_eq(
+    source: &[u8; ENCRYPTED_VALUE_SIZE],
+    source_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    destination_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    equality_proof: &CiphertextCiphertextEqualityProofContext,
+) -> Result<[u8; ENCRYPTED_VALUE_SIZE]> {
+    require!(
+        bytemuck::bytes_of(&equality_proof.first_pubkey) == source_pubkey.as_ref()
+            && bytemuck::bytes_of(&equality_proof.second_pubkey) == destination_pubkey.as_ref()
+            && bytemuck::bytes_of(&equality_proof.first_ciphertext) == source.as_ref(),
+        VeilPayError::CiphertextEqualityProofVerificationFailed
+    );
+
+    let mut destination = [0u8; ENCRYPTED_VALUE_SIZE];
+    destination.copy_from_slice(bytemuck::bytes_of(&equality_proof.second_ciphertext));
+    Ok(destination)
+}
+
+/// Check that the validity proof covers both halves of this grouped transfer ciphertext, i.e.
+/// that every decrypt handle encodes the same amount as the shared commitment, and that the
+/// sender and recipient handles were made for their registered keys. When the mint has an
+/// auditor, the third handle must be made for the auditor's key as well.
+pub fn cspl_assert_valid_transfer(
+    transfer_amount_lo: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    transfer_amount_hi: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    sender_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    recipient_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    auditor_pubkey: Option<&[u8; ELGAMAL_PUBKEY_SIZE]>,
+    validity_proof: &BatchedGroupedCiphertext3HandlesValidityProofContext,
+) -> Result<()> {
+    if let Some(auditor_pubkey) = auditor_pubkey {
+        require!(
+            bytemuck::bytes_of(&validity_proof.third_pubkey) == auditor_pubkey.as_ref(),
+            VeilPayError::ValidityProofVerificationFailed
+        );
+    }
+    require!(
+        bytemuck::bytes_of(&validity_proof.first_pubkey) == sender_pubkey.as_ref()
+            && bytemuck::bytes_of(&validity_proof.second_pubkey) == recipient_pubkey.as_ref()
+            && bytemuck::bytes_of(&validity_proof.grouped_ciphertext_lo)
+                == transfer_amount_lo.as_ref()
+            && bytemuck::bytes_of(&validity_proof.grouped_ciphertext_hi)
+                == transfer_amount_hi.as_ref(),
+        VeilPayError::ValidityProofVerificationFailed
+    );
+
+    Ok(())
+}
+
+/// Check that the pubkey validity proof (knowledge of the secret scalar) is for this key.
+pub fn cspl_assert_valid_pubkey(
+    elgamal_pubkey: &[u8; ELGAMAL_PUBKEY_SIZE],
+    pubkey_validity_proof: &PubkeyValidityProofContext,
+) -> Result<()> {
+    require!(
+        bytemuck::bytes_of(&pubkey_validity_proof.pubkey) == elgamal_pubkey.as_ref(),
+        VeilPayError::PubkeyValidityProofVerificationFailed
+    );
+
+    Ok(())
+}
+
+/// Extract one party's view of a grouped transfer ciphertext: the shared commitment with that
+/// party's decrypt handle, which they can decrypt with their own ElGamal secret key.
+pub fn grouped_ciphertext_view(
+    transfer_amount: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    handle_index: usize,
+) -> [u8; ENCRYPTED_VALUE_SIZE] {
+    let handle_start = ELGAMAL_C1_SIZE + handle_index * ELGAMAL_C2_SIZE;
+
+    let mut ciphertext = [0u8; ENCRYPTED_VALUE_SIZE];
+    ciphertext[..ELGAMAL_C1_SIZE].copy_from_slice(&transfer_amount[..ELGAMAL_C1_SIZE]);
+    ciphertext[ELGAMAL_C1_SIZE..]
+        .copy_from_slice(&transfer_amount[handle_start..handle_start + ELGAMAL_C2_SIZE]);
+    ciphertext
+}
+
+/// Homomorphically subtract one ciphertext from another (both under the same key).
 pub fn cspl_sub(
     balance: &[u8; ENCRYPTED_VALUE_SIZE],
     amount: &[u8; ENCRYPTED_VALUE_SIZE],
 ) -> Result<[u8; ENCRYPTED_VALUE_SIZE]> {
-    let balance_val = extract_encrypted_value(balance);
-    let amount_val = extract_encrypted_value(amount);
+    let (balance_commitment, balance_handle) = split_ciphertext(balance);
+    let (amount_commitment, amount_handle) = split_ciphertext(amount);
 
-    // Perform subtraction
-    let result_val = balance_val.checked_sub(amount_val).ok_or(ErrorCode::InsufficientBalance)?;
+    let commitment = subtract_ristretto(&balance_commitment, &amount_commitment)
+        .ok_or(VeilPayError::InvalidEncryption)?;
+    let handle = subtract_ristretto(&balance_handle, &amount_handle)
+        .ok_or(VeilPayError::InvalidEncryption)?;
 
-    // Re-encrypt (pack)
-    Ok(encrypt_amount(result_val))
+    Ok(join_ciphertext(&commitment, &handle))
 }
 
+/// Homomorphically add two ciphertexts (both under the same key).
 pub fn cspl_add(
     balance: &[u8; ENCRYPTED_VALUE_SIZE],
     amount: &[u8; ENCRYPTED_VALUE_SIZE],
 ) -> Result<[u8; ENCRYPTED_VALUE_SIZE]> {
-    let balance_val = extract_encrypted_value(balance);
-    let amount_val = extract_encrypted_value(amount);
+    let (balance_commitment, balance_handle) = split_ciphertext(balance);
+    let (amount_commitment, amount_handle) = split_ciphertext(amount);
+
+    let commitment = add_ristretto(&balance_commitment, &amount_commitment)
+        .ok_or(VeilPayError::InvalidEncryption)?;
+    let handle = add_ristretto(&balance_handle, &amount_handle)
+        .ok_or(VeilPayError::InvalidEncryption)?;
+
+    Ok(join_ciphertext(&commitment, &handle))
+}
+
+/// Add a lo/hi split amount (`lo + hi * 2^16`) to a ciphertext.
+pub fn cspl_add_with_lo_hi(
+    balance: &[u8; ENCRYPTED_VALUE_SIZE],
+    amount_lo: &[u8; ENCRYPTED_VALUE_SIZE],
+    amount_hi: &[u8; ENCRYPTED_VALUE_SIZE],
+) -> Result<[u8; ENCRYPTED_VALUE_SIZE]> {
+    let amount = cspl_add(amount_lo, &shift_hi(amount_hi)?)?;
+    cspl_add(balance, &amount)
+}
+
+/// Subtract a lo/hi split amount (`lo + hi * 2^16`) from a ciphertext.
+pub fn cspl_sub_with_lo_hi(
+    balance: &[u8; ENCRYPTED_VALUE_SIZE],
+    amount_lo: &[u8; ENCRYPTED_VALUE_SIZE],
+    amount_hi: &[u8; ENCRYPTED_VALUE_SIZE],
+) -> Result<[u8; ENCRYPTED_VALUE_SIZE]> {
+    let amount = cspl_add(amount_lo, &shift_hi(amount_hi)?)?;
+    cspl_sub(balance, &amount)
+}
+
+/// Split a public amount into its lo (16-bit) and hi parts.
+pub fn split_amount(amount: u64) -> (u64, u64) {
+    let lo = amount & ((1 << TRANSFER_AMOUNT_LO_BIT_LENGTH) - 1);
+    let hi = amount >> TRANSFER_AMOUNT_LO_BIT_LENGTH;
+    (lo, hi)
+}
 
-    // Perform addition
-    let result_val = balance_val.checked_add(amount_val).unwrap_or(u64::MAX);
+/// Add a public amount to a ciphertext. Only the commitment changes (`amount * G` with a
+/// zero opening), so the owner's decryption handle stays valid.
+pub fn cspl_add_amount(
+    balance: &[u8; ENCRYPTED_VALUE_SIZE],
+    amount: u64,
+) -> Result<[u8; ENCRYPTED_VALUE_SIZE]> {
+    let (balance_commitment, handle) = split_ciphertext(balance);
+    let commitment = add_ristretto(&balance_commitment, &amount_to_point(amount)?)
+        .ok_or(VeilPayError::InvalidEncryption)?;
 
-    // Re-encrypt (pack)
-    Ok(encrypt_amount(result_val))
+    Ok(join_ciphertext(&commitment, &handle))
 }
 
-pub fn cspl_decrypt(
+/// Subtract a public amount from a ciphertext.
+pub fn cspl_sub_amount(
     balance: &[u8; ENCRYPTED_VALUE_SIZE],
-) -> u64 {
-    extract_encrypted_value(balance)
+    amount: u64,
+) -> Result<[u8; ENCRYPTED_VALUE_SIZE]> {
+    let (balance_commitment, handle) = split_ciphertext(balance);
+    let commitment = subtract_ristretto(&balance_commitment, &amount_to_point(amount)?)
+        .ok_or(VeilPayError::InvalidEncryption)?;
+
+    Ok(join_ciphertext(&commitment, &handle))
 }
 
-pub fn encrypt_amount(amount: u64) -> [u8; ENCRYPTED_VALUE_SIZE] {
-    let mut encrypted = [0u8; ENCRYPTED_VALUE_SIZE];
-    let amount_bytes = amount.to_le_bytes();
+fn amount_to_point(amount: u64) -> Result<PodRistrettoPoint> {
+    let mut scalar = [0u8; 32];
+    scalar[0..8].copy_from_slice(&amount.to_le_bytes());
+
+    let point = multiply_ristretto(&PodScalar(scalar), &RISTRETTO_BASEPOINT)
+        .ok_or(VeilPayError::InvalidEncryption)?;
 
-    // Store amount in first 8 bytes
-    encrypted[0..8].copy_from_slice(&amount_bytes);
+    Ok(point)
+}
 
-    // Fill the rest with noise (hash of amount to look random)
-    let noise_seed = hashv(&[&amount_bytes, b"noise"]);
-    encrypted[8..40].copy_from_slice(&noise_seed.to_bytes());
-    
-    // Fill remaining bytes if needed (size depends on constants, assuming 64)
-    // ELGAMAL_C1_SIZE + ELGAMAL_C2_SIZE usually 64
-    // We just fill 8..40 for now, rest remains 0 or can be filled more if strictly needed.
+/// Multiply both halves of a hi ciphertext by `2^16`.
+fn shift_hi(amount_hi: &[u8; ENCRYPTED_VALUE_SIZE]) -> Result<[u8; ENCRYPTED_VALUE_SIZE]> {
+    let mut scalar = [0u8; 32];
+    scalar[0..8].copy_from_slice(&(1u64 << TRANSFER_AMOUNT_LO_BIT_LENGTH).to_le_bytes());
+    let scalar = PodScalar(scalar);
 
-    encrypted
+    let (commitment, handle) = split_ciphertext(amount_hi);
+    let commitment =
+        multiply_ristretto(&scalar, &commitment).ok_or(VeilPayError::InvalidEncryption)?;
+    let handle = multiply_ristretto(&scalar, &handle).ok_or(VeilPayError::InvalidEncryption)?;
+
+    Ok(join_ciphertext(&commitment, &handle))
 }
 
-fn extract_encrypted_value(encrypted: &[u8; ENCRYPTED_VALUE_SIZE]) -> u64 {
-    let mut value_bytes = [0u8; 8];
-    value_bytes.copy_from_slice(&encrypted[0..8]);
-    u64::from_le_bytes(value_bytes)
+fn split_ciphertext(
+    ciphertext: &[u8; ENCRYPTED_VALUE_SIZE],
+) -> (PodRistrettoPoint, PodRistrettoPoint) {
+    let mut commitment = [0u8; ELGAMAL_C1_SIZE];
+    let mut handle = [0u8; ELGAMAL_C2_SIZE];
+    commitment.copy_from_slice(&ciphertext[..ELGAMAL_C1_SIZE]);
+    handle.copy_from_slice(&ciphertext[ELGAMAL_C1_SIZE..]);
+    (PodRistrettoPoint(commitment), PodRistrettoPoint(handle))
 }
 
-pub fn generate_encrypted_tag(
-    recipient_pubkey: &Pubkey,
-    sender_secret: &[u8; 32],
-) -> [u8; 32] {
-    let recipient_bytes = recipient_pubkey.as_ref();
-    let shared_seed = [recipient_bytes, sender_secret].concat();
-    hashv(&[&shared_seed]).to_bytes()
+fn join_ciphertext(
+    commitment: &PodRistrettoPoint,
+    handle: &PodRistrettoPoint,
+) -> [u8; ENCRYPTED_VALUE_SIZE] {
+    let mut ciphertext = [0u8; ENCRYPTED_VALUE_SIZE];
+    ciphertext[..ELGAMAL_C1_SIZE].copy_from_slice(&commitment.0);
+    ciphertext[ELGAMAL_C1_SIZE..].copy_from_slice(&handle.0);
+    ciphertext
 }
 
+/// Commitment to a private transfer, published in `PrivateTransferEvent`.
+///
+/// Binds the grouped lo/hi ciphertexts, the sender nonce the transfer consumed, the
+/// recipient's owner commitment and the `encrypted_tag` emitted beside it. The program ID,
+/// the `private_transfer` instruction tag and the mint's genesis hash are hashed in first,
+/// so a commitment from another program, instruction or cluster never matches.
 pub fn generate_commitment_hash(
-    encrypted_amount: &[u8; ENCRYPTED_VALUE_SIZE],
+    genesis_hash: &[u8; 32],
+    encrypted_amount_lo: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
     sender_nonce: u64,
-    recipient_pubkey: &Pubkey,
+    recipient_commitment: &[u8; 32],
+    encrypted_tag: &[u8; 32],
 ) -> [u8; 32] {
-    let nonce_bytes = sender_nonce.to_le_bytes();
-    let combined = [encrypted_amount.as_ref(), &nonce_bytes, recipient_pubkey.as_ref()].concat();
-    hashv(&[&combined]).to_bytes()
+    hashv(&[
+        COMMITMENT_HASH_DOMAIN,
+        crate::ID.as_ref(),
+        crate::instruction::PrivateTransfer::DISCRIMINATOR,
+        genesis_hash,
+        encrypted_amount_lo,
+        encrypted_amount_hi,
+        &sender_nonce.to_le_bytes(),
+        recipient_commitment,
+        encrypted_tag,
+    ])
+    .to_bytes()
 }
 
 pub fn verify_commitment_hash(
     commitment_hash: &[u8; 32],
-    encrypted_amount: &[u8; ENCRYPTED_VALUE_SIZE],
+    genesis_hash: &[u8; 32],
+    encrypted_amount_lo: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
     sender_nonce: u64,
-    recipient_pubkey: &Pubkey,
-) -> bool {
-    let expected = generate_commitment_hash(encrypted_amount, sender_nonce, recipient_pubkey);
-    commitment_hash == expected.as_ref()
-}
-
-pub fn verify_encrypted_tag(
+    recipient_commitment: &[u8; 32],
     encrypted_tag: &[u8; 32],
-    recipient_pubkey: &Pubkey,
-    sender_secret: &[u8; 32],
 ) -> bool {
-    let expected_tag = generate_encrypted_tag(recipient_pubkey, sender_secret);
-    encrypted_tag == expected_tag.as_ref()
-}
-
-pub fn derive_stealth_address(
-    recipient_pubkey: &Pubkey,
-    sender_secret: &[u8; 32],
-) -> [u8; 32] {
-    let tag = generate_encrypted_tag(recipient_pubkey, sender_secret);
-    hashv(&[recipient_pubkey.as_ref(), &tag]).to_bytes()
-}
-
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Insufficient balance for this operation")]
-    InsufficientBalance,
-    #[msg("Invalid encryption format")]
-    InvalidEncryption,
+    let expected = generate_commitment_hash(
+        genesis_hash,
+        encrypted_amount_lo,
+        encrypted_amount_hi,
+        sender_nonce,
+        recipient_commitment,
+        encrypted_tag,
+    );
+    *commitment_hash == expected
 }
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use solana_zk_sdk::encryption::elgamal::{ElGamalCiphertext, ElGamalSecretKey};
+
+use crate::constants::*;
+use crate::utils::crypto::grouped_ciphertext_view;
+
+/// Points are compressed in batches, `double_and_compress_batch` amortises the inversion.
+const COMPRESSION_BATCH_SIZE: usize = 64;
+
+/// Baby-step sizes that keep a 32-bit or a 48-bit decode to at most `2^16` / `2^24` giant steps.
+pub const U32_BABY_STEP_BITS: u8 = 16;
+pub const U48_BABY_STEP_BITS: u8 = 24;
+
+/// Precomputed baby steps for decoding `x` from `x * G` with baby-step giant-step.
+///
+/// Each baby step `j * G` is stored under the first 8 bytes of `compress(2 * j * G)`, which is
+/// what `double_and_compress_batch` hands back. A truncated key can collide, so every match is
+/// checked against the full point before it is returned.
+pub struct DecodeTable {
+    baby_step_bits: u8,
+    giant_step: RistrettoPoint,
+    entries: HashMap<u64, u32>,
+}
+
+impl DecodeTable {
+    /// Build a table holding `2^baby_step_bits` baby steps, split across `num_threads`.
+    pub fn new(baby_step_bits: u8, num_threads: NonZeroUsize) -> Self {
+        assert!(
+            (1..=32).contains(&baby_step_bits),
+            "baby_step_bits must be within 1..=32"
+        );
+        let size = 1u64 << baby_step_bits;
+        let chunk = size.div_ceil(num_threads.get() as u64);
+
+        let keys: Vec<Vec<u64>> = thread::scope(|s| {
+            let workers: Vec<_> = (0..num_threads.get() as u64)
+                .map(|t| {
+                    let start = (t * chunk).min(size);
+                    let end = (start + chunk).min(size);
+                    s.spawn(move || baby_step_keys(start, end))
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+
+        let mut entries = HashMap::with_capacity(size as usize);
+        for (j, key) in keys.into_iter().flatten().enumerate() {
+            entries.entry(key).or_insert(j as u32);
+        }
+
+        Self {
+            baby_step_bits,
+            giant_step: RistrettoPoint::mul_base(&Scalar::from(size)),
+            entries,
+        }
+    }
+
+    /// Table sized for 32-bit values, e.g. a transfer's hi half or a pending lo balance.
+    pub fn for_u32(num_threads: NonZeroUsize) -> Self {
+        Self::new(U32_BABY_STEP_BITS, num_threads)
+    }
+
+    /// Table sized for 48-bit values, e.g. a pending hi balance.
+    ///
+    /// Its `2^24` baby steps take over 500 MB in memory and about 200 MB serialized, which
+    /// suits an indexer or desktop wallet but not a browser or phone. Those should keep the
+    /// [`for_u32`](Self::for_u32) table and apply pending balances often: `decode` walks giant
+    /// steps upward from zero, so a small hi half is still found quickly with 48 bits.
+    pub fn for_u48(num_threads: NonZeroUsize) -> Self {
+        Self::new(U48_BABY_STEP_BITS, num_threads)
+    }
+
+    pub fn baby_step_bits(&self) -> u8 {
+        self.baby_step_bits
+    }
+
+    /// Serialize as the baby-step count followed by each `(key, j)` pair, little-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.entries.len() * 12);
+        bytes.push(self.baby_step_bits);
+        for (key, j) in &self.entries {
+            bytes.extend_from_slice(&key.to_le_bytes());
+            bytes.extend_from_slice(&j.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Load a table written by `to_bytes` without recomputing the baby steps. Returns `None`
+    /// unless it holds exactly `2^baby_step_bits` distinct baby steps.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&baby_step_bits, entries) = bytes.split_first()?;
+        if !(1..=32).contains(&baby_step_bits) || entries.len() != 12 << baby_step_bits {
+            return None;
+        }
+
+        let entries: HashMap<u64, u32> = entries
+            .chunks_exact(12)
+            .map(|entry| {
+                let key = u64::from_le_bytes(entry[..8].try_into().unwrap());
+                let j = u32::from_le_bytes(entry[8..].try_into().unwrap());
+                (key, j)
+            })
+            .collect();
+        // A missing baby step would make every value that needs it undecodable
+        if entries.len() as u64 != 1u64 << baby_step_bits {
+            return None;
+        }
+
+        Some(Self {
+            baby_step_bits,
+            giant_step: RistrettoPoint::mul_base(&Scalar::from(1u64 << baby_step_bits)),
+            entries,
+        })
+    }
+
+    /// Find `x < 2^bit_length` with `x * G == target`, splitting the giant steps across
+    /// `num_threads`. Returns `None` if no such `x` exists.
+    pub fn decode(
+        &self,
+        target: &RistrettoPoint,
+        bit_length: u8,
+        num_threads: NonZeroUsize,
+    ) -> Option<u64> {
+        assert!(bit_length <= 64, "bit_length must be at most 64");
+        let giant_steps = 1u64 << bit_length.saturating_sub(self.baby_step_bits);
+        let chunk = giant_steps.div_ceil(num_threads.get() as u64);
+        let found = AtomicBool::new(false);
+
+        let value = thread::scope(|s| {
+            let workers: Vec<_> = (0..num_threads.get() as u64)
+                .map(|t| {
+                    let start = (t * chunk).min(giant_steps);
+                    let end = (start + chunk).min(giant_steps);
+                    let found = &found;
+                    s.spawn(move || self.giant_steps(target, start, end, found))
+                })
+                .collect();
+            workers
+                .into_iter()
+                .filter_map(|w| w.join().unwrap())
+                .min()
+        })?;
+
+        // Baby steps always cover 2^baby_step_bits, so a narrow decode can overshoot
+        (bit_length == 64 || value < 1u64 << bit_length).then_some(value)
+    }
+
+    /// Walk giant steps `start..end`, i.e. test `target - i * 2^baby_step_bits * G`.
+    fn giant_steps(
+        &self,
+        target: &RistrettoPoint,
+        start: u64,
+        end: u64,
+        found: &AtomicBool,
+    ) -> Option<u64> {
+        let mut point = target - self.giant_step * Scalar::from(start);
+        let mut batch = Vec::with_capacity(COMPRESSION_BATCH_SIZE);
+        let mut i = start;
+
+        while i < end && !found.load(Ordering::Relaxed) {
+            batch.clear();
+            while batch.len() < COMPRESSION_BATCH_SIZE && i + (batch.len() as u64) < end {
+                batch.push(point);
+                point -= self.giant_step;
+            }
+
+            let compressed = RistrettoPoint::double_and_compress_batch(&batch);
+            for (offset, (candidate, key)) in batch.iter().zip(&compressed).enumerate() {
+                let Some(&j) = self.entries.get(&truncate(key.as_bytes())) else {
+                    continue;
+                };
+                if RistrettoPoint::mul_base(&Scalar::from(j)) == *candidate {
+                    found.store(true, Ordering::Relaxed);
+                    return Some(((i + offset as u64) << self.baby_step_bits) + j as u64);
+                }
+            }
+            i += batch.len() as u64;
+        }
+
+        None
+    }
+}
+
+/// Decrypt a 64-byte ciphertext known to hold fewer than `2^bit_length` lamports.
+pub fn decrypt_amount(
+    secret: &ElGamalSecretKey,
+    ciphertext: &[u8; ENCRYPTED_VALUE_SIZE],
+    bit_length: u8,
+    table: &DecodeTable,
+    num_threads: NonZeroUsize,
+) -> Option<u64> {
+    let ciphertext = ElGamalCiphertext::from_bytes(ciphertext)?;
+    let target = ciphertext.decrypt(secret).target;
+    table.decode(&target, bit_length, num_threads)
+}
+
+/// Decrypt the lo/hi halves of a private transfer through the handle at `handle_index`.
+pub fn decrypt_transfer_amount(
+    secret: &ElGamalSecretKey,
+    encrypted_amount_lo: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    encrypted_amount_hi: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    handle_index: usize,
+    table: &DecodeTable,
+    num_threads: NonZeroUsize,
+) -> Option<u64> {
+    let amount_lo = decrypt_amount(
+        secret,
+        &grouped_ciphertext_view(encrypted_amount_lo, handle_index),
+        TRANSFER_AMOUNT_LO_BIT_LENGTH,
+        table,
+        num_threads,
+    )?;
+    let amount_hi = decrypt_amount(
+        secret,
+        &grouped_ciphertext_view(encrypted_amount_hi, handle_index),
+        TRANSFER_AMOUNT_HI_BIT_LENGTH,
+        table,
+        num_threads,
+    )?;
+    combine_lo_hi(amount_lo, amount_hi)
+}
+
+/// Decrypt a pending balance. Up to `MAX_PENDING_BALANCE_CREDITS` credits can pile up, so the
+/// lo half is searched over 32 bits and the hi half over 48 bits.
+pub fn decrypt_pending_balance(
+    secret: &ElGamalSecretKey,
+    pending_balance_lo: &[u8; ENCRYPTED_VALUE_SIZE],
+    pending_balance_hi: &[u8; ENCRYPTED_VALUE_SIZE],
+    table: &DecodeTable,
+    num_threads: NonZeroUsize,
+) -> Option<u64> {
+    let credit_bits = MAX_PENDING_BALANCE_CREDITS.trailing_zeros() as u8;
+    let balance_lo = decrypt_amount(
+        secret,
+        pending_balance_lo,
+        TRANSFER_AMOUNT_LO_BIT_LENGTH + credit_bits,
+        table,
+        num_threads,
+    )?;
+    let balance_hi = decrypt_amount(
+        secret,
+        pending_balance_hi,
+        TRANSFER_AMOUNT_HI_BIT_LENGTH + credit_bits,
+        table,
+        num_threads,
+    )?;
+    combine_lo_hi(balance_lo, balance_hi)
+}
+
+fn combine_lo_hi(lo: u64, hi: u64) -> Option<u64> {
+    hi.checked_mul(1 << TRANSFER_AMOUNT_LO_BIT_LENGTH)?.checked_add(lo)
+}
+
+/// Keys for baby steps `start..end`, in order.
+fn baby_step_keys(start: u64, end: u64) -> Vec<u64> {
+    let mut keys = Vec::with_capacity((end - start) as usize);
+    let mut point = if start == 0 {
+        RistrettoPoint::identity()
+    } else {
+        RistrettoPoint::mul_base(&Scalar::from(start))
+    };
+    let step = RistrettoPoint::mul_base(&Scalar::ONE);
+    let mut batch = Vec::with_capacity(COMPRESSION_BATCH_SIZE);
+
+    let mut j = start;
+    while j < end {
+        batch.clear();
+        while batch.len() < COMPRESSION_BATCH_SIZE && j < end {
+            batch.push(point);
+            point += step;
+            j += 1;
+        }
+        keys.extend(
+            RistrettoPoint::double_and_compress_batch(&batch)
+                .iter()
+                .map(|key| truncate(key.as_bytes())),
+        );
+    }
+
+    keys
+}
+
+fn truncate(compressed: &[u8; 32]) -> u64 {
+    u64::from_le_bytes(compressed[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threads() -> NonZeroUsize {
+        NonZeroUsize::new(2).unwrap()
+    }
+
+    fn point(x: u64) -> RistrettoPoint {
+        RistrettoPoint::mul_base(&Scalar::from(x))
+    }
+
+    #[test]
+    fn table_survives_a_bytes_round_trip() {
+        let table = DecodeTable::new(8, threads());
+        let loaded = DecodeTable::from_bytes(&table.to_bytes()).unwrap();
+
+        assert_eq!(loaded.baby_step_bits(), 8);
+        assert_eq!(loaded.decode(&point(54_321), 16, threads()), Some(54_321));
+    }
+
+    #[test]
+    fn from_bytes_rejects_a_short_table() {
+        let bytes = DecodeTable::new(8, threads()).to_bytes();
+
+        assert!(DecodeTable::from_bytes(&bytes[..bytes.len() - 12]).is_none());
+    }
+
+    #[test]
+    fn decode_rejects_values_past_bit_length() {
+        let table = DecodeTable::new(8, threads());
+
+        // Both sit inside the baby steps, only the bound keeps them out
+        assert_eq!(table.decode(&point(15), 4, threads()), Some(15));
+        assert_eq!(table.decode(&point(16), 4, threads()), None);
+        assert_eq!(table.decode(&point(200), 4, threads()), None);
+    }
+
+    #[test]
+    fn decode_misses_points_outside_the_table() {
+        let table = DecodeTable::new(8, threads());
+
+        assert_eq!(table.decode(&point(1 << 16), 16, threads()), None);
+        assert_eq!(
+            table.decode(
+                &RistrettoPoint::mul_base(&Scalar::from(u64::MAX)),
+                16,
+                threads()
+            ),
+            None
+        );
+    }
+}
@@ -1,7 +1,55 @@
 use anchor_lang::prelude::*;
 use solana_keccak_hasher::hashv;
+use crate::constants::{BALANCE_SEED, OWNER_COMMITMENT_DOMAIN, STEALTH_BALANCE_SEED};
+#[cfg(not(target_os = "solana"))]
+use crate::constants::{BALANCE_AUTHORITY_DOMAIN, OWNER_SECRET_DOMAIN};
 
-/// Hash owner pubkey into a fixed 32-byte commitment
-pub fn hash_owner(owner: &Pubkey) -> [u8; 32] {
-    hashv(&[owner.as_ref()]).to_bytes()
+/// Blinded commitment to the authority that controls a balance.
+///
+/// Balances are not controlled by a wallet key but by a per-mint balance authority derived
+/// from the owner's secret (see [`balance_authority_seed`]), so neither the commitment nor the
+/// balance PDA seeded with it can be linked to a wallet without that secret.
+pub fn owner_commitment(mint: &Pubkey, authority: &Pubkey) -> [u8; 32] {
+    hashv(&[OWNER_COMMITMENT_DOMAIN, mint.as_ref(), authority.as_ref()]).to_bytes()
+}
+
+/// Whether `address` is the `BALANCE_SEED` PDA for `owner_commitment` on `mint`
+pub fn is_balance_pda(
+    address: &Pubkey,
+    mint: &Pubkey,
+    owner_commitment: &[u8; 32],
+    bump: u8,
+) -> bool {
+    Pubkey::create_program_address(
+        &[BALANCE_SEED, mint.as_ref(), owner_commitment.as_ref(), &[bump]],
+        &crate::ID,
+    )
+    .is_ok_and(|expected| *address == expected)
+}
+
+/// Whether `address` is the `STEALTH_BALANCE_SEED` PDA for `stealth_pubkey` on `mint`
+pub fn is_stealth_balance_pda(
+    address: &Pubkey,
+    mint: &Pubkey,
+    stealth_pubkey: &[u8; 32],
+    bump: u8,
+) -> bool {
+    Pubkey::create_program_address(
+        &[STEALTH_BALANCE_SEED, mint.as_ref(), stealth_pubkey.as_ref(), &[bump]],
+        &crate::ID,
+    )
+    .is_ok_and(|expected| *address == expected)
+}
+
+/// Recover the owner secret from the wallet's signature over `OWNER_SECRET_MESSAGE`.
+#[cfg(not(target_os = "solana"))]
+pub fn owner_secret_from_signature(signature: &[u8; 64]) -> [u8; 32] {
+    hashv(&[OWNER_SECRET_DOMAIN, signature.as_ref()]).to_bytes()
+}
+
+/// Seed of the ed25519 keypair that controls the owner's balance on `mint`, e.g. for
+/// `Keypair::new_from_array`. Each mint gets an unrelated authority.
+#[cfg(not(target_os = "solana"))]
+pub fn balance_authority_seed(owner_secret: &[u8; 32], mint: &Pubkey) -> [u8; 32] {
+    hashv(&[BALANCE_AUTHORITY_DOMAIN, owner_secret.as_ref(), mint.as_ref()]).to_bytes()
 }
@@ -1,2 +1,8 @@
 pub mod helpers;
-pub mod crypto;
\ No newline at end of file
+pub mod crypto;
+pub mod proof;
+pub mod token;
+pub mod cpi;
+pub mod stealth;
+#[cfg(not(target_os = "solana"))]
+pub mod discrete_log;
@@ -0,0 +1,230 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar::instructions::get_instruction_relative;
+use bytemuck::Pod;
+use solana_zk_sdk::zk_elgamal_proof_program::{
+    self,
+    instruction::ProofInstruction,
+    proof_data::*,
+    state::ProofContextStateMeta,
+};
+use crate::constants::MAX_PROOF_CONTEXT_SIZE;
+use crate::errors::VeilPayError;
+use crate::state::ProofContext;
+
+/// Read a ZK ElGamal proof instruction that sits `offset` instructions away from the current
+/// one and return its verified context.
+///
+/// The proof program runs in the same transaction, so if the proof is invalid the whole
+/// transaction fails before this context can be used. The caller is still responsible for
+/// checking that the context matches the on-chain ciphertexts.
+pub fn verify_proof_instruction<T, U>(instructions_sysvar: &AccountInfo, offset: i8) -> Result<U>
+where
+    T: Pod + ZkProofData<U>,
+    U: Pod,
+{
+    let instruction = load_proof_instruction(instructions_sysvar, offset)?;
+
+    require!(
+        ProofInstruction::instruction_type(&instruction.data)
+            == Some(proof_instruction_for(T::PROOF_TYPE)),
+        VeilPayError::InvalidProofInstruction
+    );
+
+    decode_proof_instruction::<T, U>(&instruction.data)
+}
+
+/// Like [`verify_proof_instruction`], but takes the context from a proof context account
+/// recorded earlier by `verify_proof` when one is supplied.
+pub fn verify_proof<T, U>(
+    instructions_sysvar: &AccountInfo,
+    offset: i8,
+    proof_context: Option<&ProofContext>,
+) -> Result<U>
+where
+    T: Pod + ZkProofData<U>,
+    U: Pod,
+{
+    let Some(proof_context) = proof_context else {
+        return verify_proof_instruction::<T, U>(instructions_sysvar, offset);
+    };
+
+    proof_context_data::<T, U>(proof_context)
+}
+
+/// Read the context of type `U` out of a proof context account recorded by `verify_proof`.
+pub fn proof_context_data<T, U>(proof_context: &ProofContext) -> Result<U>
+where
+    T: Pod + ZkProofData<U>,
+    U: Pod,
+{
+    require!(
+        proof_context.proof_type == T::PROOF_TYPE as u8,
+        VeilPayError::InvalidProofContext
+    );
+
+    let context = bytemuck::try_from_bytes::<U>(&proof_context.context)
+        .map_err(|_| VeilPayError::InvalidProofContext)?;
+
+    Ok(*context)
+}
+
+/// Read a context state account written by the ZK ElGamal proof program itself, for proofs too
+/// large to share a transaction with the instruction that records them.
+pub fn verify_proof_context_state(
+    context_state: &AccountInfo,
+    authority: &Pubkey,
+) -> Result<(ProofType, Vec<u8>)> {
+    require_keys_eq!(
+        *context_state.owner,
+        zk_elgamal_proof_program::id(),
+        VeilPayError::InvalidProofContext
+    );
+
+    let data = context_state.try_borrow_data()?;
+    let meta = ProofContextStateMeta::try_from_bytes(&data)
+        .map_err(|_| VeilPayError::InvalidProofContext)?;
+    require_keys_eq!(
+        meta.context_state_authority,
+        *authority,
+        VeilPayError::UnauthorizedAccess
+    );
+
+    let proof_type =
+        ProofType::try_from(meta.proof_type).map_err(|_| VeilPayError::InvalidProofContext)?;
+    let context = &data[std::mem::size_of::<ProofContextStateMeta>()..];
+    require!(
+        proof_type != ProofType::Uninitialized && context.len() <= MAX_PROOF_CONTEXT_SIZE,
+        VeilPayError::InvalidProofContext
+    );
+
+    Ok((proof_type, context.to_vec()))
+}
+
+/// Read any supported proof instruction and return its proof type and raw context bytes, for
+/// recording in a proof context account.
+pub fn verify_any_proof_instruction(
+    instructions_sysvar: &AccountInfo,
+    offset: i8,
+) -> Result<(ProofType, Vec<u8>)> {
+    let instruction = load_proof_instruction(instructions_sysvar, offset)?;
+    let data = &instruction.data;
+
+    let proof_instruction = ProofInstruction::instruction_type(data)
+        .ok_or(VeilPayError::InvalidProofInstruction)?;
+
+    let context = match proof_instruction {
+        ProofInstruction::CloseContextState => {
+            return err!(VeilPayError::InvalidProofInstruction);
+        }
+        ProofInstruction::VerifyZeroCiphertext => {
+            context_bytes::<ZeroCiphertextProofData, ZeroCiphertextProofContext>(data)?
+        }
+        ProofInstruction::VerifyCiphertextCiphertextEquality => context_bytes::<
+            CiphertextCiphertextEqualityProofData,
+            CiphertextCiphertextEqualityProofContext,
+        >(data)?,
+        ProofInstruction::VerifyCiphertextCommitmentEquality => context_bytes::<
+            CiphertextCommitmentEqualityProofData,
+            CiphertextCommitmentEqualityProofContext,
+        >(data)?,
+        ProofInstruction::VerifyPubkeyValidity => {
+            context_bytes::<PubkeyValidityProofData, PubkeyValidityProofContext>(data)?
+        }
+        ProofInstruction::VerifyPercentageWithCap => {
+            context_bytes::<PercentageWithCapProofData, PercentageWithCapProofContext>(data)?
+        }
+        ProofInstruction::VerifyBatchedRangeProofU64 => {
+            context_bytes::<BatchedRangeProofU64Data, BatchedRangeProofContext>(data)?
+        }
+        ProofInstruction::VerifyBatchedRangeProofU128 => {
+            context_bytes::<BatchedRangeProofU128Data, BatchedRangeProofContext>(data)?
+        }
+        ProofInstruction::VerifyBatchedRangeProofU256 => {
+            context_bytes::<BatchedRangeProofU256Data, BatchedRangeProofContext>(data)?
+        }
+        ProofInstruction::VerifyGroupedCiphertext2HandlesValidity => context_bytes::<
+            GroupedCiphertext2HandlesValidityProofData,
+            GroupedCiphertext2HandlesValidityProofContext,
+        >(data)?,
+        ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity => context_bytes::<
+            BatchedGroupedCiphertext2HandlesValidityProofData,
+            BatchedGroupedCiphertext2HandlesValidityProofContext,
+        >(data)?,
+        ProofInstruction::VerifyGroupedCiphertext3HandlesValidity => context_bytes::<
+            GroupedCiphertext3HandlesValidityProofData,
+            GroupedCiphertext3HandlesValidityProofContext,
+        >(data)?,
+        ProofInstruction::VerifyBatchedGroupedCiphertext3HandlesValidity => context_bytes::<
+            BatchedGroupedCiphertext3HandlesValidityProofData,
+            BatchedGroupedCiphertext3HandlesValidityProofContext,
+        >(data)?,
+    };
+
+    Ok(context)
+}
+
+fn load_proof_instruction(instructions_sysvar: &AccountInfo, offset: i8) -> Result<Instruction> {
+    require!(offset != 0, VeilPayError::MissingProofInstruction);
+
+    let instruction = get_instruction_relative(offset as i64, instructions_sysvar)
+        .map_err(|_| VeilPayError::MissingProofInstruction)?;
+
+    require_keys_eq!(
+        instruction.program_id,
+        zk_elgamal_proof_program::id(),
+        VeilPayError::InvalidProofInstruction
+    );
+
+    Ok(instruction)
+}
+
+fn decode_proof_instruction<T, U>(data: &[u8]) -> Result<U>
+where
+    T: Pod + ZkProofData<U>,
+    U: Pod,
+{
+    let proof_data = ProofInstruction::proof_data::<T, U>(data)
+        .ok_or(VeilPayError::InvalidProofInstruction)?;
+
+    Ok(*proof_data.context_data())
+}
+
+fn context_bytes<T, U>(data: &[u8]) -> Result<(ProofType, Vec<u8>)>
+where
+    T: Pod + ZkProofData<U>,
+    U: Pod,
+{
+    let context = decode_proof_instruction::<T, U>(data)?;
+    Ok((T::PROOF_TYPE, bytemuck::bytes_of(&context).to_vec()))
+}
+
+fn proof_instruction_for(proof_type: ProofType) -> ProofInstruction {
+    match proof_type {
+        ProofType::Uninitialized => ProofInstruction::CloseContextState,
+        ProofType::ZeroCiphertext => ProofInstruction::VerifyZeroCiphertext,
+        ProofType::CiphertextCiphertextEquality => {
+            ProofInstruction::VerifyCiphertextCiphertextEquality
+        }
+        ProofType::CiphertextCommitmentEquality => {
+            ProofInstruction::VerifyCiphertextCommitmentEquality
+        }
+        ProofType::PubkeyValidity => ProofInstruction::VerifyPubkeyValidity,
+        ProofType::PercentageWithCap => ProofInstruction::VerifyPercentageWithCap,
+        ProofType::BatchedRangeProofU64 => ProofInstruction::VerifyBatchedRangeProofU64,
+        ProofType::BatchedRangeProofU128 => ProofInstruction::VerifyBatchedRangeProofU128,
+        ProofType::BatchedRangeProofU256 => ProofInstruction::VerifyBatchedRangeProofU256,
+        ProofType::GroupedCiphertext2HandlesValidity => {
+            ProofInstruction::VerifyGroupedCiphertext2HandlesValidity
+        }
+        ProofType::BatchedGroupedCiphertext2HandlesValidity => {
+            ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity
+        }
+        ProofType::GroupedCiphertext3HandlesValidity => {
+            ProofInstruction::VerifyGroupedCiphertext3HandlesValidity
+        }
+        ProofType::BatchedGroupedCiphertext3HandlesValidity => {
+            ProofInstruction::VerifyBatchedGroupedCiphertext3HandlesValidity
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Dual-key stealth addresses (DKSAP) over Ristretto255.
+//!
+//! A recipient publishes a meta-address made of two keys:
+//! - a view key `V = v * G`. Anyone holding `v` can find the recipient's payments.
+//! - a spend key `S`, an ElGamal public key `s^-1 * H`. Only the holder of `s` can spend.
+//!
+//! For each payment the sender picks an ephemeral scalar `r` and publishes `R = r * G`. Both
+//! sides reach the same shared point, `r * V = v * R`, and hash it to a scalar `h`. The
+//! one-time stealth key is the ElGamal public key `P = h * S`. Its secret is `s * h^-1`, so
+//! amounts encrypted to `P` can only be decrypted and proven over by the recipient. A one-byte
+//! view tag lets scanners skip almost every announcement after a single hash.
+
+use solana_curve25519::ristretto::{validate_ristretto, PodRistrettoPoint};
+
+/// Whether `pubkey` can serve as a view, spend, ephemeral or stealth key.
+pub fn validate_stealth_pubkey(pubkey: &[u8; 32]) -> bool {
+    // The all-zero encoding is the identity, which would make every shared secret the same
+    *pubkey != [0u8; 32] && validate_ristretto(&PodRistrettoPoint(*pubkey))
+}
+
+#[cfg(not(target_os = "solana"))]
+pub use off_chain::*;
+
+#[cfg(not(target_os = "solana"))]
+mod off_chain {
+    use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+    use curve25519_dalek::scalar::Scalar;
+    use solana_keccak_hasher::hashv;
+    use solana_zk_sdk::encryption::elgamal::{ElGamalKeypair, ElGamalPubkey, ElGamalSecretKey};
+
+    use crate::constants::*;
+
+    /// A one-time stealth key derived by the sender, plus what it announces on-chain.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct StealthAddress {
+        pub stealth_pubkey: [u8; 32],
+        pub ephemeral_pubkey: [u8; 32],
+        pub view_tag: u8,
+    }
+
+    /// The recipient's view and spend secrets.
+    pub struct StealthKeys {
+        view_secret: Scalar,
+        spend_secret: ElGamalSecretKey,
+    }
+
+    impl StealthKeys {
+        /// Derive both keys from the owner secret, so the wallet signature that recovers the
+        /// balance authority also recovers the meta-address.
+        pub fn from_owner_secret(owner_secret: &[u8; 32]) -> Self {
+            Self {
+                view_secret: hash_to_scalar(STEALTH_VIEW_KEY_DOMAIN, &[owner_secret]),
+                spend_secret: ElGamalSecretKey::from(hash_to_scalar(
+                    STEALTH_SPEND_KEY_DOMAIN,
+                    &[owner_secret],
+                )),
+            }
+        }
+
+        pub fn view_secret(&self) -> &Scalar {
+            &self.view_secret
+        }
+
+        pub fn view_pubkey(&self) -> [u8; 32] {
+            RistrettoPoint::mul_base(&self.view_secret)
+                .compress()
+                .to_bytes()
+        }
+
+        pub fn spend_pubkey(&self) -> [u8; 32] {
+            ElGamalPubkey::new(&self.spend_secret)
+                .get_point()
+                .compress()
+                .to_bytes()
+        }
+
+        /// ElGamal keypair for the stealth key announced with `ephemeral_pubkey`, if it
+        /// belongs to this recipient.
+        pub fn stealth_keypair(
+            &self,
+            ephemeral_pubkey: &[u8; 32],
+            stealth_pubkey: &[u8; 32],
+        ) -> Option<ElGamalKeypair> {
+            let (h, _) = shared_scalar(&self.view_secret, ephemeral_pubkey)?;
+            let secret = self.spend_secret.get_scalar() * h.invert();
+            let keypair = ElGamalKeypair::new(ElGamalSecretKey::from(secret));
+            (keypair.pubkey().get_point().compress().to_bytes() == *stealth_pubkey)
+                .then_some(keypair)
+        }
+    }
+
+    /// Derive a fresh one-time stealth key for the meta-address `(view_pubkey, spend_pubkey)`.
+    pub fn derive_stealth_address(
+        view_pubkey: &[u8; 32],
+        spend_pubkey: &[u8; 32],
+    ) -> Option<StealthAddress> {
+        let ephemeral_secret = *ElGamalSecretKey::new_rand().get_scalar();
+        derive_stealth_address_with(view_pubkey, spend_pubkey, &ephemeral_secret)
+    }
+
+    /// [`derive_stealth_address`] with a caller-chosen ephemeral secret. Reusing one links the
+    /// payments made with it.
+    pub fn derive_stealth_address_with(
+        view_pubkey: &[u8; 32],
+        spend_pubkey: &[u8; 32],
+        ephemeral_secret: &Scalar,
+    ) -> Option<StealthAddress> {
+        let spend_point = decompress(spend_pubkey)?;
+        let (h, view_tag) = shared_scalar(ephemeral_secret, view_pubkey)?;
+
+        Some(StealthAddress {
+            stealth_pubkey: (h * spend_point).compress().to_bytes(),
+            ephemeral_pubkey: RistrettoPoint::mul_base(ephemeral_secret)
+                .compress()
+                .to_bytes(),
+            view_tag,
+        })
+    }
+
+    /// Check an announcement against a meta-address using only the view secret, so a scanning
+    /// service never holds anything that can spend.
+    pub fn scan_stealth_address(
+        view_secret: &Scalar,
+        spend_pubkey: &[u8; 32],
+        announcement: &StealthAddress,
+    ) -> bool {
+        let Some((h, view_tag)) = shared_scalar(view_secret, &announcement.ephemeral_pubkey)
+        else {
+            return false;
+        };
+        if view_tag != announcement.view_tag {
+            return false;
+        }
+        decompress(spend_pubkey)
+            .is_some_and(|spend| (h * spend).compress().to_bytes() == announcement.stealth_pubkey)
+    }
+
+    /// Hash `secret * public` into the stealth scalar `h` and the view tag.
+    fn shared_scalar(secret: &Scalar, public: &[u8; 32]) -> Option<(Scalar, u8)> {
+        let shared = (secret * decompress(public)?).compress().to_bytes();
+        let h = hash_to_scalar(STEALTH_SHARED_SECRET_DOMAIN, &[&shared]);
+        if h == Scalar::ZERO {
+            return None;
+        }
+        let view_tag = hashv(&[STEALTH_VIEW_TAG_DOMAIN, &shared]).to_bytes()[0];
+        Some((h, view_tag))
+    }
+
+    fn decompress(pubkey: &[u8; 32]) -> Option<RistrettoPoint> {
+        if !super::validate_stealth_pubkey(pubkey) {
+            return None;
+        }
+        CompressedRistretto(*pubkey).decompress()
+    }
+
+    /// Reduce 512 bits of keccak output so the scalar is uniform mod the group order.
+    fn hash_to_scalar(domain: &[u8], data: &[&[u8]]) -> Scalar {
+        let mut wide = [0u8; 64];
+        for (i, half) in wide.chunks_exact_mut(32).enumerate() {
+            let counter = [i as u8];
+            let mut input = vec![domain, &counter[..]];
+            input.extend_from_slice(data);
+            half.copy_from_slice(&hashv(&input).to_bytes());
+        }
+        Scalar::from_bytes_mod_order_wide(&wide)
+    }
+}
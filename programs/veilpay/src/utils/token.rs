@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{transfer_hook, BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    instruction::transfer_checked,
+    state::Mint,
+};
+use solana_program::program::invoke_signed;
+use spl_transfer_hook_interface::onchain::add_extra_accounts_for_execute_cpi;
+use crate::errors::VeilPayError;
+
+/// Extensions that let tokens be moved or locked outside the program's control, so the vault
+/// could no longer back the confidential balances.
+const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 2] = [
+    ExtensionType::NonTransferable,
+    ExtensionType::PermanentDelegate,
+];
+
+/// Reject Token-2022 mints carrying an extension from `UNSUPPORTED_MINT_EXTENSIONS`.
+pub fn assert_supported_mint(mint_info: &AccountInfo) -> Result<()> {
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let extensions = mint.get_extension_types()?;
+    require!(
+        !extensions
+            .iter()
+            .any(|extension| UNSUPPORTED_MINT_EXTENSIONS.contains(extension)),
+        VeilPayError::UnsupportedMintExtension
+    );
+
+    Ok(())
+}
+
+/// `transfer_checked` that also forwards any transfer-hook extra accounts passed as
+/// remaining accounts. Works for both the Token and Token-2022 programs.
+///
+/// Token-2022's `onchain::invoke_transfer_checked` without its multisig handling, since the
+/// authority is always a plain signer or the mint PDA. It invokes like `cpi::transfer_lamports`
+/// so natively processed tests run the transfer and the hook.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked_with_hook<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    additional_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut instruction = transfer_checked(
+        token_program.key,
+        from.key,
+        mint.key,
+        to.key,
+        authority.key,
+        &[],
+        amount,
+        decimals,
+    )?;
+    let mut account_infos = vec![from.clone(), mint.clone(), to.clone(), authority.clone()];
+
+    // Only borrow the mint while reading it, the token program needs it during the CPI
+    let hook_program_id = {
+        let mint_data = mint.try_borrow_data()?;
+        transfer_hook::get_program_id(&StateWithExtensions::<Mint>::unpack(&mint_data)?)
+    };
+    if let Some(hook_program_id) = hook_program_id {
+        add_extra_accounts_for_execute_cpi(
+            &mut instruction,
+            &mut account_infos,
+            &hook_program_id,
+            from.clone(),
+            mint.clone(),
+            to.clone(),
+            authority.clone(),
+            amount,
+            additional_accounts,
+        )?;
+    }
+
+    invoke_signed(&instruction, &account_infos, signer_seeds)?;
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! Helpers shared by the natively processed integration tests.
+
+// Each test binary compiles its own copy and uses only some of these
+#![allow(dead_code)]
+
+use anchor_lang::AccountSerialize;
+use bytemuck::Pod;
+use solana_sdk::{
+    account::Account, account_info::AccountInfo, entrypoint::ProgramResult,
+    instruction::InstructionError, pubkey::Pubkey, rent::Rent, transaction::TransactionError,
+};
+use solana_zk_sdk::encryption::{
+    auth_encryption::AeKey,
+    elgamal::{ElGamalCiphertext, ElGamalKeypair, ElGamalPubkey},
+    pedersen::Pedersen,
+    pod::elgamal::PodElGamalPubkey,
+};
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    BatchedRangeProofU64Data, CiphertextCommitmentEqualityProofData, ZkProofData,
+};
+use veilpay::constants::*;
+use veilpay::errors::VeilPayError;
+use veilpay::state::{ConfidentialBalance, MintConfig, ProofContext, VeilPayMint};
+use veilpay::utils::{
+    crypto::{cspl_sub_amount, ZERO_CIPHERTEXT},
+    helpers::owner_commitment,
+};
+
+// Anchor's entrypoint wants the accounts to outlive the whole call
+pub fn process_instruction<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    veilpay::entry(program_id, accounts, data)
+}
+
+pub fn pod_pubkey(pubkey: &ElGamalPubkey) -> [u8; ELGAMAL_PUBKEY_SIZE] {
+    bytemuck::cast(PodElGamalPubkey::from(*pubkey))
+}
+
+pub fn program_account<T: AccountSerialize>(state: &T, space: usize) -> Account {
+    let mut data = Vec::with_capacity(space);
+    state.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    Account {
+        lamports: Rent::default().minimum_balance(space),
+        data,
+        owner: veilpay::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// A VeilPayMint over `underlying_asset` with no limits, fees, auditor or recorded supply.
+pub fn veilpay_mint(underlying_asset: Pubkey, bump: u8) -> VeilPayMint {
+    VeilPayMint {
+        version: MINT_CONFIG_VERSION,
+        authority: Pubkey::new_unique(),
+        underlying_asset,
+        config: MintConfig {
+            auditor_elgamal_pubkey: [0u8; ELGAMAL_PUBKEY_SIZE],
+            max_deposit_amount: u64::MAX,
+            max_withdraw_amount: u64::MAX,
+            fee_basis_points: 0,
+            maximum_fee: 0,
+            fee_recipient: Pubkey::new_unique(),
+            paused: false,
+        },
+        total_deposited: 0,
+        total_withdrawn: 0,
+        genesis_hash: [0u8; 32],
+        bump,
+    }
+}
+
+/// The main balance `authority` holds under `veilpay_mint`, with `available` encrypted under
+/// `elgamal` and `ae_key` and nothing pending, its rent paid by `authority` itself. Returns its
+/// address alongside it.
+pub fn balance_account(
+    veilpay_mint: &Pubkey,
+    authority: &Pubkey,
+    elgamal: &ElGamalKeypair,
+    ae_key: &AeKey,
+    available: u64,
+) -> (Pubkey, ConfidentialBalance) {
+    let commitment = owner_commitment(veilpay_mint, authority);
+    let (address, bump) = Pubkey::find_program_address(
+        &[BALANCE_SEED, veilpay_mint.as_ref(), commitment.as_ref()],
+        &veilpay::ID,
+    );
+    let state = ConfidentialBalance {
+        mint: *veilpay_mint,
+        owner_commitment: commitment,
+        elgamal_pubkey: pod_pubkey(elgamal.pubkey()),
+        encrypted_balance: elgamal.pubkey().encrypt(available).to_bytes(),
+        decryptable_available_balance: ae_key.encrypt(available).to_bytes(),
+        pending_balance_lo: ZERO_CIPHERTEXT,
+        pending_balance_hi: ZERO_CIPHERTEXT,
+        pending_balance_credit_counter: 0,
+        open_escrow_count: 0,
+        nonce: 0,
+        payer: *authority,
+        bump,
+    };
+    (address, state)
+}
+
+/// The ProofContext `verify_proof` records for `proof` on behalf of `authority`, its rent paid
+/// by `payer`.
+pub fn proof_context_account<T: Pod + ZkProofData<U>, U: Pod>(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    proof: &T,
+) -> Account {
+    let state = ProofContext {
+        authority: *authority,
+        payer: *payer,
+        proof_type: T::PROOF_TYPE as u8,
+        context: bytemuck::bytes_of(proof.context_data()).to_vec(),
+        bump: 0,
+    };
+    program_account(&state, 8 + ProofContext::LEN)
+}
+
+/// The equality and range proofs `withdraw` and `withdraw_token` check when debiting `amount`
+/// from `balance`, which holds `available` encrypted under `elgamal`.
+pub fn withdraw_proofs(
+    elgamal: &ElGamalKeypair,
+    balance: &ConfidentialBalance,
+    available: u64,
+    amount: u64,
+) -> (
+    CiphertextCommitmentEqualityProofData,
+    BatchedRangeProofU64Data,
+) {
+    let remaining = available - amount;
+    let new_balance = cspl_sub_amount(&balance.encrypted_balance, amount).unwrap();
+    let (commitment, opening) = Pedersen::new(remaining);
+    let equality_proof = CiphertextCommitmentEqualityProofData::new(
+        elgamal,
+        &ElGamalCiphertext::from_bytes(&new_balance).unwrap(),
+        &commitment,
+        &opening,
+        remaining,
+    )
+    .unwrap();
+    let range_proof = BatchedRangeProofU64Data::new(
+        vec![&commitment],
+        vec![remaining],
+        vec![BALANCE_BIT_LENGTH as usize],
+        vec![&opening],
+    )
+    .unwrap();
+    (equality_proof, range_proof)
+}
+
+/// The error `error` raised by the first instruction of a transaction.
+pub fn program_error(error: VeilPayError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+}
@@ -0,0 +1,354 @@
+//! The native SOL deposit and withdraw paths: the lamports moved and the proofs checked.
+
+mod common;
+
+use std::num::NonZeroUsize;
+
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use common::*;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    sysvar::instructions as sysvar_instructions,
+    transaction::{Transaction, TransactionError},
+};
+use solana_zk_sdk::encryption::{
+    auth_encryption::AeKey,
+    elgamal::ElGamalKeypair,
+    pedersen::{Pedersen, PedersenOpening},
+};
+use solana_zk_sdk::zk_elgamal_proof_program::{
+    instruction::ProofInstruction,
+    proof_data::{BatchedRangeProofU64Data, CiphertextCommitmentEqualityProofData},
+};
+use veilpay::constants::*;
+use veilpay::errors::VeilPayError;
+use veilpay::state::{ConfidentialBalance, VeilPayMint};
+use veilpay::utils::discrete_log::*;
+
+const STARTING_BALANCE: u64 = 1_000_000;
+const DEPOSIT_AMOUNT: u64 = 250_000;
+const WITHDRAW_AMOUNT: u64 = 400_000;
+/// The 1% withdrawal fee on `WITHDRAW_AMOUNT`
+const WITHDRAW_FEE: u64 = 4_000;
+
+struct Env {
+    context: ProgramTestContext,
+    veilpay_mint: Pubkey,
+    vault: Pubkey,
+    fee_recipient: Pubkey,
+    authority: Keypair,
+    elgamal: ElGamalKeypair,
+    balance: Pubkey,
+}
+
+/// Start a bank holding the native SOL mint with a 1% withdrawal fee, a vault backing it and a
+/// balance with `STARTING_BALANCE` available.
+async fn setup() -> Env {
+    let mut program_test =
+        ProgramTest::new("veilpay", veilpay::ID, processor!(process_instruction));
+
+    let fee_recipient = Pubkey::new_unique();
+    let (veilpay_mint, mint_bump) =
+        Pubkey::find_program_address(&[MINT_SEED, Pubkey::default().as_ref()], &veilpay::ID);
+    let mut mint = common::veilpay_mint(Pubkey::default(), mint_bump);
+    mint.config.fee_recipient = fee_recipient;
+    mint.config.fee_basis_points = 100;
+    mint.config.maximum_fee = u64::MAX;
+    mint.total_deposited = STARTING_BALANCE;
+    program_test.add_account(veilpay_mint, program_account(&mint, 8 + VeilPayMint::LEN));
+
+    // Lamports only stay in system accounts that are rent exempt
+    let rent = Rent::default().minimum_balance(0);
+    let (vault, _) = Pubkey::find_program_address(&[VAULT_SEED], &veilpay::ID);
+    program_test.add_account(
+        vault,
+        Account::new(rent + STARTING_BALANCE, 0, &system_program::ID),
+    );
+    program_test.add_account(fee_recipient, Account::new(rent, 0, &system_program::ID));
+
+    let authority = Keypair::new();
+    program_test.add_account(
+        authority.pubkey(),
+        Account::new(1_000_000_000, 0, &system_program::ID),
+    );
+    let elgamal = ElGamalKeypair::new_rand();
+    let (balance, state) = balance_account(
+        &veilpay_mint,
+        &authority.pubkey(),
+        &elgamal,
+        &AeKey::new_rand(),
+        STARTING_BALANCE,
+    );
+    program_test.add_account(
+        balance,
+        program_account(&state, 8 + ConfidentialBalance::LEN),
+    );
+
+    Env {
+        context: program_test.start_with_context().await,
+        veilpay_mint,
+        vault,
+        fee_recipient,
+        authority,
+        elgamal,
+        balance,
+    }
+}
+
+async fn send(env: &mut Env, instructions: &[Instruction]) -> Result<(), BanksClientError> {
+    let blockhash = env.context.banks_client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&env.context.payer.pubkey()),
+        &[&env.context.payer, &env.authority],
+        blockhash,
+    );
+    env.context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+}
+
+async fn lamports(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+async fn load<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+/// A `deposit` of `amount` lamports crediting a ciphertext of `credited`, preceded by an
+/// honest proof that the ciphertext encrypts `credited`.
+fn deposit(env: &Env, amount: u64, credited: u64) -> [Instruction; 2] {
+    let encrypted_amount = env.elgamal.pubkey().encrypt(credited);
+    // The program compares against the commitment `amount * G`, i.e. a zero opening
+    let opening = PedersenOpening::default();
+    let equality_proof = ProofInstruction::VerifyCiphertextCommitmentEquality.encode_verify_proof(
+        None,
+        &CiphertextCommitmentEqualityProofData::new(
+            &env.elgamal,
+            &encrypted_amount,
+            &Pedersen::with(credited, &opening),
+            &opening,
+            credited,
+        )
+        .unwrap(),
+    );
+    let deposit = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::Deposit {
+            veilpay_mint: env.veilpay_mint,
+            confidential_balance: env.balance,
+            vault: env.vault,
+            signer: env.authority.pubkey(),
+            system_program: system_program::ID,
+            instructions_sysvar: sysvar_instructions::ID,
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::Deposit {
+            amount,
+            encrypted_amount: encrypted_amount.to_bytes(),
+            equality_proof_instruction_offset: -1,
+        }
+        .data(),
+    };
+    [equality_proof, deposit]
+}
+
+#[tokio::test]
+async fn deposit_credits_the_pending_balance() {
+    let mut env = setup().await;
+    let vault_lamports = lamports(&mut env.context, env.vault).await;
+
+    let deposit = deposit(&env, DEPOSIT_AMOUNT, DEPOSIT_AMOUNT);
+    send(&mut env, &deposit).await.unwrap();
+
+    assert_eq!(
+        lamports(&mut env.context, env.vault).await,
+        vault_lamports + DEPOSIT_AMOUNT
+    );
+    let mint: VeilPayMint = load(&mut env.context, env.veilpay_mint).await;
+    assert_eq!(mint.total_deposited, STARTING_BALANCE + DEPOSIT_AMOUNT);
+    let balance: ConfidentialBalance = load(&mut env.context, env.balance).await;
+    let threads = NonZeroUsize::new(2).unwrap();
+    assert_eq!(
+        decrypt_pending_balance(
+            env.elgamal.secret(),
+            &balance.pending_balance_lo,
+            &balance.pending_balance_hi,
+            &DecodeTable::for_u32(threads),
+            threads,
+        ),
+        Some(DEPOSIT_AMOUNT)
+    );
+}
+
+#[tokio::test]
+async fn deposit_rejects_a_ciphertext_of_another_amount() {
+    let mut env = setup().await;
+
+    // Deposit one lamport but credit a ciphertext of a million, with an honest proof of that
+    let deposit = deposit(&env, 1, 1_000_000);
+    let error = send(&mut env, &deposit).await.unwrap_err().unwrap();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VeilPayError::EqualityProofVerificationFailed.into())
+        )
+    );
+}
+
+/// Store each proof as a ProofContext the authority paid for and return their addresses.
+fn seed_proof_contexts(env: &mut Env, accounts: [Account; 2]) -> [Pubkey; 2] {
+    accounts.map(|account| {
+        let address = Pubkey::new_unique();
+        env.context.set_account(&address, &account.into());
+        address
+    })
+}
+
+/// A `withdraw` of `amount` to `destination`, taking its proofs from `proof_contexts`.
+fn withdraw(
+    env: &Env,
+    amount: u64,
+    destination: Pubkey,
+    proof_contexts: [Pubkey; 2],
+) -> Instruction {
+    let authority = env.authority.pubkey();
+    Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::Withdraw {
+            veilpay_mint: env.veilpay_mint,
+            confidential_balance: env.balance,
+            vault: env.vault,
+            signer: authority,
+            system_program: system_program::ID,
+            destination,
+            fee_recipient: env.fee_recipient,
+            instructions_sysvar: sysvar_instructions::ID,
+            proof_context_payer: authority,
+            equality_proof_context: Some(proof_contexts[0]),
+            range_proof_context: Some(proof_contexts[1]),
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::Withdraw {
+            amount,
+            new_decryptable_available_balance: AeKey::new_rand()
+                .encrypt(STARTING_BALANCE.saturating_sub(amount))
+                .to_bytes(),
+            equality_proof_instruction_offset: 0,
+            range_proof_instruction_offset: 0,
+        }
+        .data(),
+    }
+}
+
+#[tokio::test]
+async fn withdraw_pays_out_the_amount_less_the_fee() {
+    let mut env = setup().await;
+    let authority = env.authority.pubkey();
+    let balance: ConfidentialBalance = load(&mut env.context, env.balance).await;
+    let (equality_proof, range_proof) =
+        withdraw_proofs(&env.elgamal, &balance, STARTING_BALANCE, WITHDRAW_AMOUNT);
+    let proof_contexts = seed_proof_contexts(
+        &mut env,
+        [
+            proof_context_account(&authority, &authority, &equality_proof),
+            proof_context_account(&authority, &authority, &range_proof),
+        ],
+    );
+    let (vault_lamports, fee_recipient_lamports) = (
+        lamports(&mut env.context, env.vault).await,
+        lamports(&mut env.context, env.fee_recipient).await,
+    );
+
+    // The authority withdraws to itself and gets the rent of both proof contexts back too
+    let proof_context_rent = lamports(&mut env.context, proof_contexts[0]).await * 2;
+    let authority_lamports = lamports(&mut env.context, authority).await;
+    let withdraw = withdraw(&env, WITHDRAW_AMOUNT, authority, proof_contexts);
+    send(&mut env, &[withdraw]).await.unwrap();
+
+    assert_eq!(
+        lamports(&mut env.context, authority).await,
+        authority_lamports + proof_context_rent + WITHDRAW_AMOUNT - WITHDRAW_FEE
+    );
+    assert_eq!(
+        lamports(&mut env.context, env.fee_recipient).await,
+        fee_recipient_lamports + WITHDRAW_FEE
+    );
+    assert_eq!(
+        lamports(&mut env.context, env.vault).await,
+        vault_lamports - WITHDRAW_AMOUNT
+    );
+    let mint: VeilPayMint = load(&mut env.context, env.veilpay_mint).await;
+    assert_eq!(mint.total_withdrawn, WITHDRAW_AMOUNT);
+    let balance: ConfidentialBalance = load(&mut env.context, env.balance).await;
+    let threads = NonZeroUsize::new(2).unwrap();
+    assert_eq!(
+        decrypt_amount(
+            env.elgamal.secret(),
+            &balance.encrypted_balance,
+            32,
+            &DecodeTable::for_u32(threads),
+            threads,
+        ),
+        Some(STARTING_BALANCE - WITHDRAW_AMOUNT)
+    );
+}
+
+#[tokio::test]
+async fn withdraw_rejects_overdrawing_the_balance() {
+    let mut env = setup().await;
+
+    // The remainder would be negative, so no honest equality proof for it exists. The best the
+    // owner can do is prove some other ciphertext, here a fresh encryption of zero.
+    let remaining = 0u64;
+    let (commitment, opening) = Pedersen::new(remaining);
+    let equality_proof = CiphertextCommitmentEqualityProofData::new(
+        &env.elgamal,
+        &env.elgamal.pubkey().encrypt(remaining),
+        &commitment,
+        &opening,
+        remaining,
+    )
+    .unwrap();
+    let range_proof = BatchedRangeProofU64Data::new(
+        vec![&commitment],
+        vec![remaining],
+        vec![BALANCE_BIT_LENGTH as usize],
+        vec![&opening],
+    )
+    .unwrap();
+    let authority = env.authority.pubkey();
+    let proof_contexts = seed_proof_contexts(
+        &mut env,
+        [
+            proof_context_account(&authority, &authority, &equality_proof),
+            proof_context_account(&authority, &authority, &range_proof),
+        ],
+    );
+    let withdraw = withdraw(
+        &env,
+        STARTING_BALANCE + 1,
+        Pubkey::new_unique(),
+        proof_contexts,
+    );
+
+    let error = send(&mut env, &[withdraw]).await.unwrap_err().unwrap();
+    assert_eq!(
+        error,
+        program_error(VeilPayError::EqualityProofVerificationFailed)
+    );
+}
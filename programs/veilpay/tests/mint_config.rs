@@ -0,0 +1,136 @@
+//! update_mint_config: what the mint authority may change once the mint exists.
+
+mod common;
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use common::*;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use veilpay::constants::*;
+use veilpay::errors::VeilPayError;
+use veilpay::state::{MintConfig, VeilPayMint};
+
+struct Env {
+    context: ProgramTestContext,
+    veilpay_mint: Pubkey,
+    authority: Keypair,
+    config: MintConfig,
+}
+
+/// Start a bank holding the native SOL mint with a 1% fee, controlled by `Env::authority`.
+async fn setup() -> Env {
+    let mut program_test =
+        ProgramTest::new("veilpay", veilpay::ID, processor!(process_instruction));
+
+    let authority = Keypair::new();
+    let (veilpay_mint, bump) =
+        Pubkey::find_program_address(&[MINT_SEED, Pubkey::default().as_ref()], &veilpay::ID);
+    let mut mint = common::veilpay_mint(Pubkey::default(), bump);
+    mint.authority = authority.pubkey();
+    mint.config.fee_basis_points = 100;
+    mint.config.maximum_fee = 5_000;
+    program_test.add_account(veilpay_mint, program_account(&mint, 8 + VeilPayMint::LEN));
+
+    Env {
+        context: program_test.start_with_context().await,
+        veilpay_mint,
+        authority,
+        config: mint.config,
+    }
+}
+
+async fn update_mint_config(
+    env: &mut Env,
+    signer: &Keypair,
+    config: MintConfig,
+) -> Result<(), TransactionError> {
+    let instruction = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::UpdateMintConfig {
+            veilpay_mint: env.veilpay_mint,
+            authority: signer.pubkey(),
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::UpdateMintConfig { config }.data(),
+    };
+    let blockhash = env
+        .context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&env.context.payer.pubkey()),
+        &[&env.context.payer, signer],
+        blockhash,
+    );
+    env.context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|error| error.unwrap())
+}
+
+#[tokio::test]
+async fn update_mint_config_keeps_the_fee() {
+    let mut env = setup().await;
+    let authority = env.authority.insecure_clone();
+
+    let raised = MintConfig {
+        fee_basis_points: MAX_FEE_BASIS_POINTS,
+        ..env.config
+    };
+    let uncapped = MintConfig {
+        maximum_fee: u64::MAX,
+        ..env.config
+    };
+    let redirected = MintConfig {
+        fee_recipient: authority.pubkey(),
+        ..env.config
+    };
+    for config in [raised, uncapped, redirected] {
+        assert_eq!(
+            update_mint_config(&mut env, &authority, config).await,
+            Err(program_error(VeilPayError::FeeConfigurationImmutable))
+        );
+    }
+
+    // Everything else is still the authority's to change
+    let paused = MintConfig {
+        max_deposit_amount: 1_000,
+        paused: true,
+        ..env.config
+    };
+    update_mint_config(&mut env, &authority, paused)
+        .await
+        .unwrap();
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.veilpay_mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint = VeilPayMint::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(mint.config, paused);
+}
+
+#[tokio::test]
+async fn update_mint_config_needs_the_mint_authority() {
+    let mut env = setup().await;
+    let config = MintConfig {
+        paused: true,
+        ..env.config
+    };
+
+    assert_eq!(
+        update_mint_config(&mut env, &Keypair::new(), config).await,
+        Err(program_error(VeilPayError::UnauthorizedAccess))
+    );
+}
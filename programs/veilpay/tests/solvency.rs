@@ -0,0 +1,171 @@
+//! verify_solvency against native SOL and token vaults, and the VeilPayMint supply counters.
+
+mod common;
+
+use anchor_lang::{prelude::ProgramError, system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    state::{Account as TokenAccount, AccountState},
+};
+use common::*;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use veilpay::constants::*;
+use veilpay::errors::VeilPayError;
+use veilpay::state::VeilPayMint;
+
+const TOTAL_DEPOSITED: u64 = 5_000_000;
+const TOTAL_WITHDRAWN: u64 = 2_000_000;
+const OUTSTANDING: u64 = TOTAL_DEPOSITED - TOTAL_WITHDRAWN;
+
+/// A VeilPayMint with `OUTSTANDING` recorded as deposited and not yet withdrawn.
+fn solvent_mint(underlying_asset: Pubkey, bump: u8) -> VeilPayMint {
+    VeilPayMint {
+        total_deposited: TOTAL_DEPOSITED,
+        total_withdrawn: TOTAL_WITHDRAWN,
+        ..veilpay_mint(underlying_asset, bump)
+    }
+}
+
+fn token_vault(mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    let mut data = vec![0u8; TokenAccount::LEN];
+    TokenAccount {
+        mint,
+        owner,
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    }
+    .pack_into_slice(&mut data);
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token_2022::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Run verify_solvency for a VeilPayMint over `underlying_asset` whose vault holds
+/// `vault_amount`, passing `vault_override` in place of the real vault if given.
+async fn verify_solvency(
+    underlying_asset: Pubkey,
+    vault_amount: u64,
+    vault_override: Option<Pubkey>,
+) -> Result<(), TransactionError> {
+    let mut program_test =
+        ProgramTest::new("veilpay", veilpay::ID, processor!(process_instruction));
+
+    let (mint, bump) =
+        Pubkey::find_program_address(&[MINT_SEED, underlying_asset.as_ref()], &veilpay::ID);
+    program_test.add_account(
+        mint,
+        program_account(&solvent_mint(underlying_asset, bump), 8 + VeilPayMint::LEN),
+    );
+
+    let vault = if underlying_asset == Pubkey::default() {
+        let (vault, _) = Pubkey::find_program_address(&[VAULT_SEED], &veilpay::ID);
+        program_test.add_account(vault, Account::new(vault_amount, 0, &system_program::ID));
+        vault
+    } else {
+        let (vault, _) = Pubkey::find_program_address(&[VAULT_SEED, mint.as_ref()], &veilpay::ID);
+        program_test.add_account(vault, token_vault(underlying_asset, mint, vault_amount));
+        vault
+    };
+    if let Some(other) = vault_override {
+        program_test.add_account(other, token_vault(underlying_asset, mint, u64::MAX));
+    }
+
+    let context = program_test.start_with_context().await;
+    let instruction = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::VerifySolvency {
+            veilpay_mint: mint,
+            vault: vault_override.unwrap_or(vault),
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::VerifySolvency {}.data(),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|error| error.unwrap())
+}
+
+#[tokio::test]
+async fn native_vault_must_cover_outstanding_deposits() {
+    assert_eq!(
+        verify_solvency(Pubkey::default(), OUTSTANDING, None).await,
+        Ok(())
+    );
+    assert_eq!(
+        verify_solvency(Pubkey::default(), OUTSTANDING - 1, None).await,
+        Err(program_error(VeilPayError::VaultInsolvent))
+    );
+}
+
+#[tokio::test]
+async fn token_vault_must_cover_outstanding_deposits() {
+    let underlying_asset = Pubkey::new_unique();
+    assert_eq!(
+        verify_solvency(underlying_asset, OUTSTANDING, None).await,
+        Ok(())
+    );
+    assert_eq!(
+        verify_solvency(underlying_asset, OUTSTANDING - 1, None).await,
+        Err(program_error(VeilPayError::VaultInsolvent))
+    );
+}
+
+#[tokio::test]
+async fn solvency_is_checked_against_the_mint_vault_only() {
+    // A well-funded token account at any other address does not count
+    let underlying_asset = Pubkey::new_unique();
+    assert_eq!(
+        verify_solvency(underlying_asset, 0, Some(Pubkey::new_unique())).await,
+        Err(program_error(VeilPayError::AccountNotFound))
+    );
+}
+
+#[test]
+fn supply_counters_reject_overflow() {
+    let overflow = ProgramError::Custom(VeilPayError::ArithmeticOverflow.into());
+
+    let mut mint = solvent_mint(Pubkey::default(), 0);
+    mint.total_deposited = u64::MAX;
+    assert_eq!(
+        mint.record_deposit(1).map_err(ProgramError::from),
+        Err(overflow.clone())
+    );
+    assert_eq!(mint.total_deposited, u64::MAX);
+
+    mint.total_withdrawn = u64::MAX;
+    assert_eq!(
+        mint.record_withdrawal(1).map_err(ProgramError::from),
+        Err(overflow.clone())
+    );
+    assert_eq!(mint.total_withdrawn, u64::MAX);
+
+    // More withdrawn than deposited can only mean a broken counter
+    let mut mint = solvent_mint(Pubkey::default(), 0);
+    mint.record_withdrawal(TOTAL_DEPOSITED).unwrap();
+    assert_eq!(
+        mint.outstanding().map_err(ProgramError::from),
+        Err(overflow)
+    );
+}
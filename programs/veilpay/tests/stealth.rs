@@ -0,0 +1,56 @@
+//! Stealth address derivation, scanning and spending, entirely off-chain.
+
+use veilpay::utils::stealth::*;
+
+fn recipient_keys(seed: u8) -> StealthKeys {
+    StealthKeys::from_owner_secret(&[seed; 32])
+}
+
+#[test]
+fn recipient_detects_and_spends_with_their_keys() {
+    let recipient = recipient_keys(1);
+    let address =
+        derive_stealth_address(&recipient.view_pubkey(), &recipient.spend_pubkey()).unwrap();
+
+    assert!(validate_stealth_pubkey(&address.stealth_pubkey));
+    assert!(scan_stealth_address(
+        recipient.view_secret(),
+        &recipient.spend_pubkey(),
+        &address
+    ));
+
+    let keypair = recipient
+        .stealth_keypair(&address.ephemeral_pubkey, &address.stealth_pubkey)
+        .expect("stealth key belongs to the recipient");
+    let ciphertext = keypair.pubkey().encrypt(42u64);
+    assert_eq!(ciphertext.decrypt_u32(keypair.secret()), Some(42));
+}
+
+#[test]
+fn other_recipients_do_not_match() {
+    let recipient = recipient_keys(1);
+    let other = recipient_keys(2);
+    let address =
+        derive_stealth_address(&recipient.view_pubkey(), &recipient.spend_pubkey()).unwrap();
+
+    assert!(!scan_stealth_address(
+        other.view_secret(),
+        &other.spend_pubkey(),
+        &address
+    ));
+    assert!(other
+        .stealth_keypair(&address.ephemeral_pubkey, &address.stealth_pubkey)
+        .is_none());
+}
+
+#[test]
+fn every_payment_gets_a_fresh_stealth_key() {
+    let recipient = recipient_keys(1);
+    let first =
+        derive_stealth_address(&recipient.view_pubkey(), &recipient.spend_pubkey()).unwrap();
+    let second =
+        derive_stealth_address(&recipient.view_pubkey(), &recipient.spend_pubkey()).unwrap();
+
+    assert_ne!(first.stealth_pubkey, second.stealth_pubkey);
+    assert_ne!(first.ephemeral_pubkey, second.ephemeral_pubkey);
+}
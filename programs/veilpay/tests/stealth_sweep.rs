@@ -0,0 +1,290 @@
+//! sweep_stealth merging stealth balances into the owner's main balance.
+//!
+//! The stealth balances and proof contexts that `stealth_transfer` and `verify_proof` would
+//! have written are seeded directly, since those instructions need CPIs that only run on-chain.
+
+mod common;
+
+use std::num::NonZeroUsize;
+
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use common::*;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use solana_zk_sdk::encryption::{
+    auth_encryption::AeKey,
+    elgamal::{ElGamalCiphertext, ElGamalKeypair, ElGamalPubkey},
+    pedersen::PedersenOpening,
+};
+use solana_zk_sdk::zk_elgamal_proof_program::proof_data::{
+    CiphertextCiphertextEqualityProofData, ProofType, ZkProofData,
+};
+use veilpay::constants::*;
+use veilpay::errors::VeilPayError;
+use veilpay::state::{ConfidentialBalance, ProofContext};
+use veilpay::utils::{crypto::*, discrete_log::*, stealth::*};
+
+const STARTING_BALANCE: u64 = 1_000_000;
+const STEALTH_AMOUNTS: [u64; 2] = [300_000, 45_000];
+
+/// A stealth balance holding `amount` as a pending lo/hi credit, as `stealth_transfer` leaves it.
+struct StealthPayment {
+    address: Pubkey,
+    keypair: ElGamalKeypair,
+    state: ConfidentialBalance,
+}
+
+impl StealthPayment {
+    fn new(veilpay_mint: &Pubkey, keys: &StealthKeys, amount: u64, payer: &Pubkey) -> Self {
+        let address = derive_stealth_address(&keys.view_pubkey(), &keys.spend_pubkey()).unwrap();
+        let keypair = keys
+            .stealth_keypair(&address.ephemeral_pubkey, &address.stealth_pubkey)
+            .unwrap();
+        let (balance, bump) = Pubkey::find_program_address(
+            &[
+                STEALTH_BALANCE_SEED,
+                veilpay_mint.as_ref(),
+                address.stealth_pubkey.as_ref(),
+            ],
+            &veilpay::ID,
+        );
+        let (amount_lo, amount_hi) = split_amount(amount);
+        let state = ConfidentialBalance {
+            mint: *veilpay_mint,
+            owner_commitment: [0u8; 32],
+            elgamal_pubkey: address.stealth_pubkey,
+            encrypted_balance: ZERO_CIPHERTEXT,
+            decryptable_available_balance: [0u8; AE_CIPHERTEXT_SIZE],
+            pending_balance_lo: keypair.pubkey().encrypt(amount_lo).to_bytes(),
+            pending_balance_hi: keypair.pubkey().encrypt(amount_hi).to_bytes(),
+            pending_balance_credit_counter: 1,
+            open_escrow_count: 0,
+            nonce: 0,
+            payer: *payer,
+            bump,
+        };
+        Self {
+            address: balance,
+            keypair,
+            state,
+        }
+    }
+
+    /// What the recipient records with `verify_proof`, paid for by `payer`: the stealth total
+    /// re-encrypted under `destination`.
+    fn sweep_proof(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        destination: &ElGamalPubkey,
+        amount: u64,
+    ) -> ProofContext {
+        let total = cspl_add_with_lo_hi(
+            &self.state.encrypted_balance,
+            &self.state.pending_balance_lo,
+            &self.state.pending_balance_hi,
+        )
+        .unwrap();
+        let opening = PedersenOpening::new_rand();
+        let proof = CiphertextCiphertextEqualityProofData::new(
+            &self.keypair,
+            destination,
+            &ElGamalCiphertext::from_bytes(&total).unwrap(),
+            &destination.encrypt_with(amount, &opening),
+            &opening,
+            amount,
+        )
+        .unwrap();
+        proof.verify_proof().unwrap();
+
+        ProofContext {
+            authority: *authority,
+            payer: *payer,
+            proof_type: ProofType::CiphertextCiphertextEquality as u8,
+            context: bytemuck::bytes_of(proof.context_data()).to_vec(),
+            bump: 0,
+        }
+    }
+}
+
+struct Env {
+    context: ProgramTestContext,
+    authority: Keypair,
+    elgamal: ElGamalKeypair,
+    balance: Pubkey,
+    stealth_payer: Pubkey,
+    proof_context_payer: Pubkey,
+    payments: Vec<(Pubkey, Pubkey)>,
+}
+
+async fn setup(sweep_to: Option<&ElGamalPubkey>) -> Env {
+    let mut program_test =
+        ProgramTest::new("veilpay", veilpay::ID, processor!(process_instruction));
+
+    let veilpay_mint = Pubkey::new_unique();
+    let authority = Keypair::new();
+    let elgamal = ElGamalKeypair::new_rand();
+    let (balance, main_balance) = balance_account(
+        &veilpay_mint,
+        &authority.pubkey(),
+        &elgamal,
+        &AeKey::new_rand(),
+        STARTING_BALANCE,
+    );
+    program_test.add_account(
+        balance,
+        program_account(&main_balance, 8 + ConfidentialBalance::LEN),
+    );
+    // The sender's wallet paid for the stealth balances, the owner's relayer for the proofs
+    let stealth_payer = Pubkey::new_unique();
+    let proof_context_payer = Pubkey::new_unique();
+    for payer in [stealth_payer, proof_context_payer] {
+        program_test.add_account(payer, Account::new(1_000_000_000, 0, &system_program::ID));
+    }
+
+    let keys = StealthKeys::from_owner_secret(&[7u8; 32]);
+    let sweep_to = sweep_to.unwrap_or(elgamal.pubkey());
+    let mut payments = Vec::new();
+    for amount in STEALTH_AMOUNTS {
+        let payment = StealthPayment::new(&veilpay_mint, &keys, amount, &stealth_payer);
+        let proof_context = Pubkey::new_unique();
+        program_test.add_account(
+            payment.address,
+            program_account(&payment.state, 8 + ConfidentialBalance::LEN),
+        );
+        program_test.add_account(
+            proof_context,
+            program_account(
+                &payment.sweep_proof(&authority.pubkey(), &proof_context_payer, sweep_to, amount),
+                8 + ProofContext::LEN,
+            ),
+        );
+        payments.push((payment.address, proof_context));
+    }
+
+    Env {
+        context: program_test.start_with_context().await,
+        authority,
+        elgamal,
+        balance,
+        stealth_payer,
+        proof_context_payer,
+        payments,
+    }
+}
+
+async fn lamports(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+async fn sweep(env: &mut Env) -> Result<(), BanksClientError> {
+    let mut accounts = veilpay::accounts::SweepStealth {
+        destination_balance: env.balance,
+        owner: env.authority.pubkey(),
+        proof_context_payer: env.proof_context_payer,
+    }
+    .to_account_metas(None);
+    for (stealth_balance, proof_context) in &env.payments {
+        accounts.push(AccountMeta::new(*stealth_balance, false));
+        accounts.push(AccountMeta::new(env.stealth_payer, false));
+        accounts.push(AccountMeta::new(*proof_context, false));
+    }
+    let total = STARTING_BALANCE + STEALTH_AMOUNTS.iter().sum::<u64>();
+    let instruction = Instruction {
+        program_id: veilpay::ID,
+        accounts,
+        data: veilpay::instruction::SweepStealth {
+            new_decryptable_available_balance: AeKey::new_rand().encrypt(total).to_bytes(),
+        }
+        .data(),
+    };
+
+    let blockhash = env.context.banks_client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&env.context.payer.pubkey()),
+        &[&env.context.payer, &env.authority],
+        blockhash,
+    );
+    env.context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+}
+
+#[tokio::test]
+async fn sweep_merges_stealth_balances_and_closes_them() {
+    let mut env = setup(None).await;
+    let stealth_payer_before = lamports(&mut env.context, env.stealth_payer).await;
+    let proof_context_payer_before = lamports(&mut env.context, env.proof_context_payer).await;
+
+    sweep(&mut env).await.unwrap();
+
+    for (stealth_balance, proof_context) in env.payments.clone() {
+        for closed in [stealth_balance, proof_context] {
+            let account = env.context.banks_client.get_account(closed).await.unwrap();
+            assert!(account.is_none());
+        }
+    }
+    // Each payer gets back the rent it paid
+    assert_eq!(
+        lamports(&mut env.context, env.stealth_payer).await,
+        stealth_payer_before + 2 * Rent::default().minimum_balance(8 + ConfidentialBalance::LEN)
+    );
+    assert_eq!(
+        lamports(&mut env.context, env.proof_context_payer).await,
+        proof_context_payer_before + 2 * Rent::default().minimum_balance(8 + ProofContext::LEN)
+    );
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.balance)
+        .await
+        .unwrap()
+        .unwrap();
+    let balance = ConfidentialBalance::try_deserialize(&mut account.data.as_slice()).unwrap();
+    let threads = NonZeroUsize::new(2).unwrap();
+    assert_eq!(
+        decrypt_amount(
+            env.elgamal.secret(),
+            &balance.encrypted_balance,
+            32,
+            &DecodeTable::for_u32(threads),
+            threads,
+        ),
+        Some(STARTING_BALANCE + STEALTH_AMOUNTS.iter().sum::<u64>())
+    );
+}
+
+#[tokio::test]
+async fn sweep_rejects_proofs_for_another_key() {
+    let other = ElGamalKeypair::new_rand();
+    let mut env = setup(Some(other.pubkey())).await;
+
+    let error = sweep(&mut env).await.unwrap_err().unwrap();
+    assert_eq!(
+        error,
+        program_error(VeilPayError::CiphertextEqualityProofVerificationFailed)
+    );
+}
+
+#[tokio::test]
+async fn sweep_refunds_only_the_rent_payers() {
+    let mut env = setup(None).await;
+    env.stealth_payer = env.authority.pubkey();
+    let error = sweep(&mut env).await.unwrap_err().unwrap();
+    assert_eq!(error, program_error(VeilPayError::RentPayerMismatch));
+
+    let mut env = setup(None).await;
+    env.proof_context_payer = env.authority.pubkey();
+    let error = sweep(&mut env).await.unwrap_err().unwrap();
+    assert_eq!(error, program_error(VeilPayError::RentPayerMismatch));
+}
@@ -0,0 +1,547 @@
+//! Token-2022 underlying mints: rejected extensions, transfer fees on deposit_token and
+//! transfer hooks on withdraw_token.
+
+mod common;
+
+use std::num::NonZeroUsize;
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        non_transferable::NonTransferable,
+        permanent_delegate::PermanentDelegate,
+        transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+        transfer_hook::{TransferHook, TransferHookAccount},
+        BaseStateWithExtensionsMut, Extension, ExtensionType, StateWithExtensions,
+        StateWithExtensionsMut,
+    },
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use bytemuck::Pod;
+use common::*;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    sysvar::instructions as sysvar_instructions,
+    transaction::{Transaction, TransactionError},
+};
+use solana_zk_sdk::encryption::{
+    auth_encryption::AeKey,
+    elgamal::ElGamalKeypair,
+    pedersen::{Pedersen, PedersenOpening},
+};
+use solana_zk_sdk::zk_elgamal_proof_program::{
+    instruction::ProofInstruction, proof_data::CiphertextCommitmentEqualityProofData,
+};
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::{
+    get_extra_account_metas_address,
+    instruction::{ExecuteInstruction, TransferHookInstruction},
+};
+use veilpay::constants::*;
+use veilpay::errors::VeilPayError;
+use veilpay::state::{ConfidentialBalance, VeilPayMint};
+use veilpay::utils::{discrete_log::*, token::assert_supported_mint};
+
+const DECIMALS: u8 = 6;
+const USER_TOKENS: u64 = 1_000_000;
+const DEPOSIT_AMOUNT: u64 = 10_000;
+const FEE_BASIS_POINTS: u16 = 100;
+/// What the vault receives from `DEPOSIT_AMOUNT` after the 1% transfer fee
+const NET_DEPOSIT_AMOUNT: u64 = 9_900;
+const WITHDRAW_AMOUNT: u64 = 5_000;
+/// VeilPay's own 1% withdrawal fee on `WITHDRAW_AMOUNT`
+const WITHDRAW_FEE: u64 = 50;
+
+/// Token-2022 mint data carrying `extensions`, each initialised by `init`.
+fn mint_data(
+    extensions: &[ExtensionType],
+    init: impl FnOnce(&mut StateWithExtensionsMut<Mint>),
+) -> Vec<u8> {
+    let space = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+    let mut data = vec![0u8; space];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    init(&mut state);
+    state.base = Mint {
+        decimals: DECIMALS,
+        is_initialized: true,
+        ..Mint::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    data
+}
+
+fn transfer_fee_mint() -> Vec<u8> {
+    mint_data(&[ExtensionType::TransferFeeConfig], |state| {
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: FEE_BASIS_POINTS.into(),
+        };
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = fee;
+        config.newer_transfer_fee = fee;
+    })
+}
+
+fn check_mint(data: &mut [u8]) -> Result<(), ProgramError> {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mint_info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        data,
+        &spl_token_2022::ID,
+        false,
+        0,
+    );
+    assert_supported_mint(&mint_info).map_err(ProgramError::from)
+}
+
+#[test]
+fn mints_that_can_move_vault_tokens_are_rejected() {
+    let unsupported = ProgramError::Custom(VeilPayError::UnsupportedMintExtension.into());
+
+    let mut non_transferable = mint_data(&[ExtensionType::NonTransferable], |state| {
+        state.init_extension::<NonTransferable>(true).unwrap();
+    });
+    assert_eq!(check_mint(&mut non_transferable), Err(unsupported.clone()));
+
+    let mut permanent_delegate = mint_data(&[ExtensionType::PermanentDelegate], |state| {
+        state
+            .init_extension::<PermanentDelegate>(true)
+            .unwrap()
+            .delegate = Some(Pubkey::new_unique()).try_into().unwrap();
+    });
+    assert_eq!(check_mint(&mut permanent_delegate), Err(unsupported));
+
+    // A transfer fee only changes what the vault receives, deposit_token credits that
+    assert_eq!(check_mint(&mut transfer_fee_mint()), Ok(()));
+}
+
+fn transfer_hook_mint(hook_program_id: Pubkey) -> Vec<u8> {
+    mint_data(&[ExtensionType::TransferHook], |state| {
+        let hook = state.init_extension::<TransferHook>(true).unwrap();
+        hook.program_id = Some(hook_program_id).try_into().unwrap();
+    })
+}
+
+/// A Token-2022 account holding `amount` of `mint`, with the account extension `E` its mint
+/// requires.
+fn token_account<E: Extension + Pod + Default>(
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Account {
+    let space = ExtensionType::try_calculate_account_len::<TokenAccount>(&[E::TYPE]).unwrap();
+    let mut data = vec![0u8; space];
+    let mut state =
+        StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut data).unwrap();
+    state.init_extension::<E>(true).unwrap();
+    state.base = TokenAccount {
+        mint,
+        owner,
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    Account {
+        lamports: Rent::default().minimum_balance(space),
+        data,
+        owner: spl_token_2022::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+struct Env {
+    context: ProgramTestContext,
+    underlying_mint: Pubkey,
+    veilpay_mint: Pubkey,
+    vault: Pubkey,
+    fee_tokens: Pubkey,
+    user: Keypair,
+    user_tokens: Pubkey,
+    elgamal: ElGamalKeypair,
+    balance: Pubkey,
+}
+
+/// Start a bank with a 1% transfer-fee mint, its VeilPayMint and vault, and a user holding
+/// `USER_TOKENS` with an empty confidential balance.
+async fn setup() -> Env {
+    let program_test = ProgramTest::new("veilpay", veilpay::ID, processor!(process_instruction));
+    setup_with(
+        program_test,
+        Pubkey::new_unique(),
+        transfer_fee_mint(),
+        token_account::<TransferFeeAmount>,
+        0,
+    )
+    .await
+}
+
+/// Start `program_test` with `underlying_mint` holding `mint_data`, its VeilPayMint charging a
+/// 1% withdrawal fee, and a user holding `USER_TOKENS`. The user's confidential balance has
+/// `available` on it, backed by as many tokens in the vault.
+async fn setup_with(
+    mut program_test: ProgramTest,
+    underlying_mint: Pubkey,
+    mint_data: Vec<u8>,
+    token_account: fn(Pubkey, Pubkey, u64) -> Account,
+    available: u64,
+) -> Env {
+    program_test.add_account(
+        underlying_mint,
+        Account {
+            lamports: Rent::default().minimum_balance(mint_data.len()),
+            data: mint_data,
+            owner: spl_token_2022::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (veilpay_mint, mint_bump) =
+        Pubkey::find_program_address(&[MINT_SEED, underlying_mint.as_ref()], &veilpay::ID);
+    let mut mint = common::veilpay_mint(underlying_mint, mint_bump);
+    mint.config.auditor_elgamal_pubkey = pod_pubkey(ElGamalKeypair::new_rand().pubkey());
+    mint.config.fee_basis_points = FEE_BASIS_POINTS;
+    mint.config.maximum_fee = u64::MAX;
+    mint.total_deposited = available;
+    program_test.add_account(veilpay_mint, program_account(&mint, 8 + VeilPayMint::LEN));
+
+    let (vault, _) =
+        Pubkey::find_program_address(&[VAULT_SEED, veilpay_mint.as_ref()], &veilpay::ID);
+    program_test.add_account(
+        vault,
+        token_account(underlying_mint, veilpay_mint, available),
+    );
+
+    let fee_tokens = Pubkey::new_unique();
+    program_test.add_account(
+        fee_tokens,
+        token_account(underlying_mint, mint.config.fee_recipient, 0),
+    );
+
+    let user = Keypair::new();
+    let user_tokens = Pubkey::new_unique();
+    program_test.add_account(
+        user_tokens,
+        token_account(underlying_mint, user.pubkey(), USER_TOKENS),
+    );
+
+    let elgamal = ElGamalKeypair::new_rand();
+    let (balance, state) = balance_account(
+        &veilpay_mint,
+        &user.pubkey(),
+        &elgamal,
+        &AeKey::new_rand(),
+        available,
+    );
+    program_test.add_account(
+        balance,
+        program_account(&state, 8 + ConfidentialBalance::LEN),
+    );
+
+    Env {
+        context: program_test.start_with_context().await,
+        underlying_mint,
+        veilpay_mint,
+        vault,
+        fee_tokens,
+        user,
+        user_tokens,
+        elgamal,
+        balance,
+    }
+}
+
+/// Deposit `DEPOSIT_AMOUNT`, proving the ciphertext encrypts `proven_amount`.
+async fn deposit_token(env: &mut Env, proven_amount: u64) -> Result<(), TransactionError> {
+    let encrypted_amount = env.elgamal.pubkey().encrypt(proven_amount);
+    // The program compares against the commitment `amount * G`, i.e. a zero opening
+    let opening = PedersenOpening::default();
+    let commitment = Pedersen::with(proven_amount, &opening);
+    let equality_proof = ProofInstruction::VerifyCiphertextCommitmentEquality.encode_verify_proof(
+        None,
+        &CiphertextCommitmentEqualityProofData::new(
+            &env.elgamal,
+            &encrypted_amount,
+            &commitment,
+            &opening,
+            proven_amount,
+        )
+        .unwrap(),
+    );
+    let deposit = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::DepositToken {
+            veilpay_mint: env.veilpay_mint,
+            confidential_balance: env.balance,
+            underlying_mint: env.underlying_mint,
+            vault: env.vault,
+            user_token_account: env.user_tokens,
+            signer: env.user.pubkey(),
+            token_program: spl_token_2022::ID,
+            instructions_sysvar: sysvar_instructions::ID,
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::DepositToken {
+            amount: DEPOSIT_AMOUNT,
+            encrypted_amount: encrypted_amount.to_bytes(),
+            equality_proof_instruction_offset: -1,
+        }
+        .data(),
+    };
+
+    let blockhash = env
+        .context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[equality_proof, deposit],
+        Some(&env.context.payer.pubkey()),
+        &[&env.context.payer, &env.user],
+        blockhash,
+    );
+    env.context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|error| error.unwrap())
+}
+
+#[tokio::test]
+async fn deposit_token_credits_the_amount_after_transfer_fees() {
+    let mut env = setup().await;
+    deposit_token(&mut env, NET_DEPOSIT_AMOUNT).await.unwrap();
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.veilpay_mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint = VeilPayMint::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(mint.total_deposited, NET_DEPOSIT_AMOUNT);
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.balance)
+        .await
+        .unwrap()
+        .unwrap();
+    let balance = ConfidentialBalance::try_deserialize(&mut account.data.as_slice()).unwrap();
+    let threads = NonZeroUsize::new(2).unwrap();
+    assert_eq!(
+        decrypt_pending_balance(
+            env.elgamal.secret(),
+            &balance.pending_balance_lo,
+            &balance.pending_balance_hi,
+            &DecodeTable::for_u32(threads),
+            threads,
+        ),
+        Some(NET_DEPOSIT_AMOUNT)
+    );
+}
+
+#[tokio::test]
+async fn deposit_token_rejects_a_proof_of_the_gross_amount() {
+    let mut env = setup().await;
+
+    // The proof sits at index 0, so the deposit fails at index 1
+    let error = deposit_token(&mut env, DEPOSIT_AMOUNT).await.unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VeilPayError::EqualityProofVerificationFailed.into())
+        )
+    );
+}
+
+/// A transfer hook that counts its executions in the extra account it is given.
+fn count_transfers(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    match TransferHookInstruction::unpack(data)? {
+        TransferHookInstruction::Execute { .. } => {
+            // Source, mint, destination, authority and validation account, then the counter
+            let mut counter = accounts[5].try_borrow_mut_data()?;
+            let count = u64::from_le_bytes(counter[..8].try_into().unwrap());
+            counter[..8].copy_from_slice(&(count + 1).to_le_bytes());
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+async fn token_amount(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+#[tokio::test]
+async fn withdraw_token_runs_the_transfer_hook() {
+    let mut program_test =
+        ProgramTest::new("veilpay", veilpay::ID, processor!(process_instruction));
+    // The hook has no SBF build, keep it native when the tests run against one
+    program_test.prefer_bpf(false);
+    let hook_program_id = Pubkey::new_unique();
+    program_test.add_program(
+        "count_transfers",
+        hook_program_id,
+        processor!(count_transfers),
+    );
+
+    // The hook asks for one extra account, a counter it owns
+    let underlying_mint = Pubkey::new_unique();
+    let counter = Pubkey::new_unique();
+    program_test.add_account(
+        counter,
+        Account {
+            lamports: Rent::default().minimum_balance(8),
+            data: vec![0u8; 8],
+            owner: hook_program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let extra_metas = [ExtraAccountMeta::new_with_pubkey(&counter, false, true).unwrap()];
+    let space = ExtraAccountMetaList::size_of(extra_metas.len()).unwrap();
+    let mut data = vec![0u8; space];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_metas).unwrap();
+    let validation = get_extra_account_metas_address(&underlying_mint, &hook_program_id);
+    program_test.add_account(
+        validation,
+        Account {
+            lamports: Rent::default().minimum_balance(space),
+            data,
+            owner: hook_program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let mut env = setup_with(
+        program_test,
+        underlying_mint,
+        transfer_hook_mint(hook_program_id),
+        token_account::<TransferHookAccount>,
+        DEPOSIT_AMOUNT,
+    )
+    .await;
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.balance)
+        .await
+        .unwrap()
+        .unwrap();
+    let balance = ConfidentialBalance::try_deserialize(&mut account.data.as_slice()).unwrap();
+    let (equality_proof, range_proof) =
+        withdraw_proofs(&env.elgamal, &balance, DEPOSIT_AMOUNT, WITHDRAW_AMOUNT);
+    let user = env.user.pubkey();
+    let mut proof_contexts = Vec::new();
+    for account in [
+        proof_context_account(&user, &user, &equality_proof),
+        proof_context_account(&user, &user, &range_proof),
+    ] {
+        let address = Pubkey::new_unique();
+        env.context.set_account(&address, &account.into());
+        proof_contexts.push(address);
+    }
+
+    let mut withdraw = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::WithdrawToken {
+            veilpay_mint: env.veilpay_mint,
+            confidential_balance: env.balance,
+            underlying_mint: env.underlying_mint,
+            vault: env.vault,
+            destination_token_account: env.user_tokens,
+            fee_token_account: env.fee_tokens,
+            signer: user,
+            token_program: spl_token_2022::ID,
+            instructions_sysvar: sysvar_instructions::ID,
+            proof_context_payer: user,
+            equality_proof_context: Some(proof_contexts[0]),
+            range_proof_context: Some(proof_contexts[1]),
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::WithdrawToken {
+            amount: WITHDRAW_AMOUNT,
+            new_decryptable_available_balance: AeKey::new_rand()
+                .encrypt(DEPOSIT_AMOUNT - WITHDRAW_AMOUNT)
+                .to_bytes(),
+            equality_proof_instruction_offset: 0,
+            range_proof_instruction_offset: 0,
+        }
+        .data(),
+    };
+    withdraw.accounts.extend([
+        AccountMeta::new(counter, false),
+        AccountMeta::new_readonly(hook_program_id, false),
+        AccountMeta::new_readonly(validation, false),
+    ]);
+
+    let blockhash = env
+        .context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[withdraw],
+        Some(&env.context.payer.pubkey()),
+        &[&env.context.payer, &env.user],
+        blockhash,
+    );
+    env.context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        token_amount(&mut env.context, env.user_tokens).await,
+        USER_TOKENS + WITHDRAW_AMOUNT - WITHDRAW_FEE
+    );
+    assert_eq!(
+        token_amount(&mut env.context, env.fee_tokens).await,
+        WITHDRAW_FEE
+    );
+    // The hook ran for both the payout and the fee transfer
+    let account = env
+        .context
+        .banks_client
+        .get_account(counter)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data, 2u64.to_le_bytes());
+}
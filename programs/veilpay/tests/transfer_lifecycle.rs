@@ -0,0 +1,1019 @@
+//! create_transfer → claim_transfer / cancel_transfer flows, and private_transfer checks.
+//!
+//! Anchor 0.32 creates accounts through an invoke that only exists on-chain, so the natively
+//! processed tests seed the PendingTransfer that `create_transfer` would have written. The full
+//! end-to-end tests run against the SBF build and are ignored otherwise; `anchor test` runs
+//! them.
+
+mod common;
+
+use std::num::NonZeroUsize;
+
+use anchor_lang::{
+    error::ErrorCode, system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use common::*;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    sysvar::instructions as sysvar_instructions,
+    transaction::{Transaction, TransactionError},
+};
+use solana_zk_sdk::encryption::{
+    auth_encryption::AeKey,
+    elgamal::{ElGamalCiphertext, ElGamalKeypair, ElGamalPubkey},
+    grouped_elgamal::{GroupedElGamal, GroupedElGamalCiphertext},
+    pedersen::{Pedersen, PedersenCommitment, PedersenOpening},
+};
+use solana_zk_sdk::zk_elgamal_proof_program::{
+    self,
+    instruction::{ContextStateInfo, ProofInstruction},
+    proof_data::{
+        BatchedGroupedCiphertext3HandlesValidityProofData, BatchedRangeProofContext,
+        BatchedRangeProofU128Data, CiphertextCommitmentEqualityProofData,
+    },
+    state::ProofContextState,
+};
+use veilpay::constants::*;
+use veilpay::errors::VeilPayError;
+use veilpay::state::{ConfidentialBalance, PendingTransfer, VeilPayMint};
+use veilpay::utils::{crypto::*, discrete_log::*, helpers::*};
+
+const STARTING_BALANCE: u64 = 1_000_000;
+const TRANSFER_AMOUNT: u64 = 300_000;
+const SEEDED_NONCE: u64 = 3;
+const EQUALITY_PROOF_CONTEXT_ID: u64 = 7;
+const VALIDITY_PROOF_CONTEXT_ID: u64 = 8;
+const RANGE_PROOF_CONTEXT_ID: u64 = 9;
+const GENESIS_HASH: [u8; 32] = [42; 32];
+const ENCRYPTED_TAG: [u8; 32] = [7; 32];
+
+fn threads() -> NonZeroUsize {
+    NonZeroUsize::new(2).unwrap()
+}
+
+struct User {
+    /// Balance authority derived from the user's wallet, the wallet itself never signs
+    authority: Keypair,
+    commitment: [u8; 32],
+    elgamal: ElGamalKeypair,
+    ae_key: AeKey,
+    balance: Pubkey,
+}
+
+impl User {
+    fn new(veilpay_mint: &Pubkey) -> Self {
+        let wallet = Keypair::new();
+        let signature: [u8; 64] = wallet.sign_message(OWNER_SECRET_MESSAGE).into();
+        let owner_secret = owner_secret_from_signature(&signature);
+        let authority =
+            Keypair::new_from_array(balance_authority_seed(&owner_secret, veilpay_mint));
+        let commitment = owner_commitment(veilpay_mint, &authority.pubkey());
+        let (balance, _) = Pubkey::find_program_address(
+            &[BALANCE_SEED, veilpay_mint.as_ref(), commitment.as_ref()],
+            &veilpay::ID,
+        );
+        Self {
+            authority,
+            commitment,
+            elgamal: ElGamalKeypair::new_rand(),
+            ae_key: AeKey::new_rand(),
+            balance,
+        }
+    }
+}
+
+struct Env {
+    context: ProgramTestContext,
+    veilpay_mint: Pubkey,
+    sender: User,
+    recipient: User,
+    auditor: ElGamalKeypair,
+    /// Pays the rent of every account the users create, their authorities hold no lamports
+    payer: Keypair,
+}
+
+/// `TRANSFER_AMOUNT` split into lo/hi halves, each encrypted once for the sender, recipient and
+/// auditor.
+struct TransferCiphertexts {
+    amount_lo: u64,
+    amount_hi: u64,
+    lo: GroupedElGamalCiphertext<3>,
+    hi: GroupedElGamalCiphertext<3>,
+    opening_lo: PedersenOpening,
+    opening_hi: PedersenOpening,
+}
+
+impl TransferCiphertexts {
+    fn new(sender: &ElGamalPubkey, recipient: &ElGamalPubkey, auditor: &ElGamalPubkey) -> Self {
+        let (amount_lo, amount_hi) = split_amount(TRANSFER_AMOUNT);
+        let opening_lo = PedersenOpening::new_rand();
+        let opening_hi = PedersenOpening::new_rand();
+        Self {
+            amount_lo,
+            amount_hi,
+            lo: GroupedElGamal::encrypt_with([sender, recipient, auditor], amount_lo, &opening_lo),
+            hi: GroupedElGamal::encrypt_with([sender, recipient, auditor], amount_hi, &opening_hi),
+            opening_lo,
+            opening_hi,
+        }
+    }
+
+    fn lo_bytes(&self) -> [u8; GROUPED_ENCRYPTED_VALUE_SIZE] {
+        self.lo.to_bytes().try_into().unwrap()
+    }
+
+    fn hi_bytes(&self) -> [u8; GROUPED_ENCRYPTED_VALUE_SIZE] {
+        self.hi.to_bytes().try_into().unwrap()
+    }
+}
+
+/// `user`'s balance with `available` encrypted, its rent paid by `payer`.
+fn user_balance(
+    veilpay_mint: Pubkey,
+    user: &User,
+    payer: &Pubkey,
+    available: u64,
+    open_escrow_count: u64,
+) -> Account {
+    let (_, mut balance) = balance_account(
+        &veilpay_mint,
+        &user.authority.pubkey(),
+        &user.elgamal,
+        &user.ae_key,
+        available,
+    );
+    balance.open_escrow_count = open_escrow_count;
+    balance.nonce = SEEDED_NONCE;
+    balance.payer = *payer;
+    program_account(&balance, 8 + ConfidentialBalance::LEN)
+}
+
+fn pending_transfer_address(
+    env_mint: &Pubkey,
+    sender: &User,
+    recipient: &User,
+    nonce: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PENDING_TRANSFER_SEED,
+            env_mint.as_ref(),
+            sender.commitment.as_ref(),
+            recipient.commitment.as_ref(),
+            &nonce.to_le_bytes(),
+        ],
+        &veilpay::ID,
+    )
+}
+
+/// Start a bank holding the native SOL mint, a sender with `STARTING_BALANCE` available and an
+/// empty recipient. `seed_pending_transfer` also adds the PendingTransfer `create_transfer`
+/// would have written at `SEEDED_NONCE`.
+async fn setup(seed_pending_transfer: bool) -> (Env, Pubkey) {
+    let mut program_test =
+        ProgramTest::new("veilpay", veilpay::ID, processor!(process_instruction));
+
+    let (veilpay_mint, mint_bump) =
+        Pubkey::find_program_address(&[MINT_SEED, Pubkey::default().as_ref()], &veilpay::ID);
+    let sender = User::new(&veilpay_mint);
+    let recipient = User::new(&veilpay_mint);
+    let auditor = ElGamalKeypair::new_rand();
+
+    let mut mint = common::veilpay_mint(Pubkey::default(), mint_bump);
+    mint.config.auditor_elgamal_pubkey = pod_pubkey(auditor.pubkey());
+    mint.total_deposited = STARTING_BALANCE;
+    mint.genesis_hash = GENESIS_HASH;
+    program_test.add_account(veilpay_mint, program_account(&mint, 8 + VeilPayMint::LEN));
+
+    let payer = Keypair::new();
+    program_test.add_account(
+        payer.pubkey(),
+        Account::new(1_000_000_000, 0, &system_program::ID),
+    );
+    let sender_available = if seed_pending_transfer {
+        STARTING_BALANCE - TRANSFER_AMOUNT
+    } else {
+        STARTING_BALANCE
+    };
+    program_test.add_account(
+        sender.balance,
+        user_balance(
+            veilpay_mint,
+            &sender,
+            &payer.pubkey(),
+            sender_available,
+            seed_pending_transfer as u64,
+        ),
+    );
+    program_test.add_account(
+        recipient.balance,
+        user_balance(veilpay_mint, &recipient, &payer.pubkey(), 0, 0),
+    );
+
+    let (pending_transfer, pending_transfer_bump) =
+        pending_transfer_address(&veilpay_mint, &sender, &recipient, SEEDED_NONCE);
+    if seed_pending_transfer {
+        let transfer = TransferCiphertexts::new(
+            sender.elgamal.pubkey(),
+            recipient.elgamal.pubkey(),
+            auditor.pubkey(),
+        );
+        let state = PendingTransfer {
+            mint: veilpay_mint,
+            sender_commitment: sender.commitment,
+            recipient_commitment: recipient.commitment,
+            recipient_elgamal_pubkey: pod_pubkey(recipient.elgamal.pubkey()),
+            encrypted_amount_lo: transfer.lo_bytes(),
+            encrypted_amount_hi: transfer.hi_bytes(),
+            timestamp: 0,
+            nonce: SEEDED_NONCE,
+            payer: payer.pubkey(),
+            bump: pending_transfer_bump,
+        };
+        program_test.add_account(
+            pending_transfer,
+            program_account(&state, 8 + PendingTransfer::LEN),
+        );
+    }
+
+    let context = program_test.start_with_context().await;
+    let env = Env {
+        context,
+        veilpay_mint,
+        sender,
+        recipient,
+        auditor,
+        payer,
+    };
+    (env, pending_transfer)
+}
+
+async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+async fn lamports(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+async fn load_balance(context: &mut ProgramTestContext, address: Pubkey) -> ConfidentialBalance {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .expect("balance account exists");
+    ConfidentialBalance::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn proof_context_address(authority: &Pubkey, context_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            PROOF_CONTEXT_SEED,
+            authority.as_ref(),
+            &context_id.to_le_bytes(),
+        ],
+        &veilpay::ID,
+    )
+    .0
+}
+
+fn verify_proof_instruction(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    context_id: u64,
+    proof_instruction_offset: i8,
+    zk_proof_context: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::VerifyProof {
+            proof_context: proof_context_address(authority, context_id),
+            authority: *authority,
+            payer: *payer,
+            system_program: system_program::ID,
+            instructions_sysvar: sysvar_instructions::ID,
+            zk_proof_context,
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::VerifyProof {
+            context_id,
+            proof_instruction_offset,
+        }
+        .data(),
+    }
+}
+
+/// Validity proof that every handle of `transfer` encrypts the same amount.
+fn validity_proof_data(
+    env: &Env,
+    transfer: &TransferCiphertexts,
+) -> BatchedGroupedCiphertext3HandlesValidityProofData {
+    BatchedGroupedCiphertext3HandlesValidityProofData::new(
+        env.sender.elgamal.pubkey(),
+        env.recipient.elgamal.pubkey(),
+        env.auditor.pubkey(),
+        &transfer.lo,
+        &transfer.hi,
+        transfer.amount_lo,
+        transfer.amount_hi,
+        &transfer.opening_lo,
+        &transfer.opening_hi,
+    )
+    .unwrap()
+}
+
+/// Equality proof that the sender's balance less `transfer` encrypts `remaining`, with the
+/// commitment and opening the range proof has to reuse.
+fn equality_proof_data(
+    env: &Env,
+    sender_balance: &ConfidentialBalance,
+    transfer: &TransferCiphertexts,
+    remaining: u64,
+) -> (
+    CiphertextCommitmentEqualityProofData,
+    PedersenCommitment,
+    PedersenOpening,
+) {
+    let new_balance = ElGamalCiphertext::from_bytes(
+        &cspl_sub_with_lo_hi(
+            &sender_balance.encrypted_balance,
+            &grouped_ciphertext_view(&transfer.lo_bytes(), SENDER_HANDLE_INDEX),
+            &grouped_ciphertext_view(&transfer.hi_bytes(), SENDER_HANDLE_INDEX),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let (commitment, opening) = Pedersen::new(remaining);
+    let proof = CiphertextCommitmentEqualityProofData::new(
+        &env.sender.elgamal,
+        &new_balance,
+        &commitment,
+        &opening,
+        remaining,
+    )
+    .unwrap();
+    (proof, commitment, opening)
+}
+
+/// Range proof over the remaining balance and the lo/hi halves of `transfer`.
+fn range_proof_data(
+    remaining: u64,
+    commitment: &PedersenCommitment,
+    opening: &PedersenOpening,
+    transfer: &TransferCiphertexts,
+) -> BatchedRangeProofU128Data {
+    let (padding_commitment, padding_opening) = Pedersen::new(0u64);
+    BatchedRangeProofU128Data::new(
+        vec![
+            commitment,
+            &transfer.lo.commitment,
+            &transfer.hi.commitment,
+            &padding_commitment,
+        ],
+        vec![remaining, transfer.amount_lo, transfer.amount_hi, 0],
+        vec![
+            BALANCE_BIT_LENGTH as usize,
+            TRANSFER_AMOUNT_LO_BIT_LENGTH as usize,
+            TRANSFER_AMOUNT_HI_BIT_LENGTH as usize,
+            RANGE_PROOF_PADDING_BIT_LENGTH as usize,
+        ],
+        vec![
+            opening,
+            &transfer.opening_lo,
+            &transfer.opening_hi,
+            &padding_opening,
+        ],
+    )
+    .unwrap()
+}
+
+/// Escrow `TRANSFER_AMOUNT` from the sender to the recipient, returning the PendingTransfer.
+///
+/// All three proofs are recorded with `verify_proof` first, together they do not fit in one
+/// transaction with `create_transfer`.
+async fn create_transfer(env: &mut Env) -> Pubkey {
+    let sender_balance = load_balance(&mut env.context, env.sender.balance).await;
+    let (pending_transfer, _) = pending_transfer_address(
+        &env.veilpay_mint,
+        &env.sender,
+        &env.recipient,
+        sender_balance.nonce,
+    );
+    let wallet = env.sender.authority.insecure_clone();
+    let payer = env.payer.insecure_clone();
+    let transfer = TransferCiphertexts::new(
+        env.sender.elgamal.pubkey(),
+        env.recipient.elgamal.pubkey(),
+        env.auditor.pubkey(),
+    );
+    let (lo_bytes, hi_bytes) = (transfer.lo_bytes(), transfer.hi_bytes());
+
+    // Every handle encrypts the same amount
+    let validity_proof = ProofInstruction::VerifyBatchedGroupedCiphertext3HandlesValidity
+        .encode_verify_proof(None, &validity_proof_data(env, &transfer));
+    send(
+        &mut env.context,
+        &[
+            validity_proof,
+            verify_proof_instruction(
+                &wallet.pubkey(),
+                &payer.pubkey(),
+                VALIDITY_PROOF_CONTEXT_ID,
+                -1,
+                None,
+            ),
+        ],
+        &[&wallet, &payer],
+    )
+    .await
+    .unwrap();
+
+    // Prove the remaining balance against a fresh commitment to it
+    let remaining = STARTING_BALANCE - TRANSFER_AMOUNT;
+    let (equality_proof_data, commitment, opening) =
+        equality_proof_data(env, &sender_balance, &transfer, remaining);
+    let equality_proof = ProofInstruction::VerifyCiphertextCommitmentEquality
+        .encode_verify_proof(None, &equality_proof_data);
+    send(
+        &mut env.context,
+        &[
+            equality_proof,
+            verify_proof_instruction(
+                &wallet.pubkey(),
+                &payer.pubkey(),
+                EQUALITY_PROOF_CONTEXT_ID,
+                -1,
+                None,
+            ),
+        ],
+        &[&wallet, &payer],
+    )
+    .await
+    .unwrap();
+
+    let range_proof_data = range_proof_data(remaining, &commitment, &opening, &transfer);
+
+    // A range proof fills a whole transaction, so the proof program writes it to its own
+    // context state account and verify_proof copies it from there
+    let zk_context = Keypair::new();
+    let zk_context_space = std::mem::size_of::<ProofContextState<BatchedRangeProofContext>>();
+    let create_zk_context = anchor_lang::solana_program::system_instruction::create_account(
+        &env.context.payer.pubkey(),
+        &zk_context.pubkey(),
+        Rent::default().minimum_balance(zk_context_space),
+        zk_context_space as u64,
+        &zk_elgamal_proof_program::id(),
+    );
+    send(&mut env.context, &[create_zk_context], &[&zk_context])
+        .await
+        .unwrap();
+    let range_proof = ProofInstruction::VerifyBatchedRangeProofU128.encode_verify_proof(
+        Some(ContextStateInfo {
+            context_state_account: &zk_context.pubkey(),
+            context_state_authority: &wallet.pubkey(),
+        }),
+        &range_proof_data,
+    );
+    send(&mut env.context, &[range_proof], &[]).await.unwrap();
+    send(
+        &mut env.context,
+        &[verify_proof_instruction(
+            &wallet.pubkey(),
+            &payer.pubkey(),
+            RANGE_PROOF_CONTEXT_ID,
+            0,
+            Some(zk_context.pubkey()),
+        )],
+        &[&wallet, &payer],
+    )
+    .await
+    .unwrap();
+
+    let create_transfer = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::CreateTransfer {
+            veilpay_mint: env.veilpay_mint,
+            sender_balance: env.sender.balance,
+            recipient_balance: env.recipient.balance,
+            pending_transfer,
+            sender: wallet.pubkey(),
+            payer: payer.pubkey(),
+            system_program: system_program::ID,
+            instructions_sysvar: sysvar_instructions::ID,
+            proof_context_payer: payer.pubkey(),
+            equality_proof_context: Some(proof_context_address(
+                &wallet.pubkey(),
+                EQUALITY_PROOF_CONTEXT_ID,
+            )),
+            validity_proof_context: Some(proof_context_address(
+                &wallet.pubkey(),
+                VALIDITY_PROOF_CONTEXT_ID,
+            )),
+            range_proof_context: Some(proof_context_address(
+                &wallet.pubkey(),
+                RANGE_PROOF_CONTEXT_ID,
+            )),
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::CreateTransfer {
+            encrypted_amount_lo: lo_bytes,
+            encrypted_amount_hi: hi_bytes,
+            recipient_commitment: env.recipient.commitment,
+            new_decryptable_available_balance: env.sender.ae_key.encrypt(remaining).to_bytes(),
+            equality_proof_instruction_offset: 0,
+            validity_proof_instruction_offset: 0,
+            range_proof_instruction_offset: 0,
+        }
+        .data(),
+    };
+    send(&mut env.context, &[create_transfer], &[&wallet, &payer])
+        .await
+        .unwrap();
+
+    pending_transfer
+}
+
+fn claim_instruction(env: &Env, pending_transfer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::ClaimTransfer {
+            recipient_balance: env.recipient.balance,
+            sender_balance: env.sender.balance,
+            pending_transfer,
+            recipient: env.recipient.authority.pubkey(),
+            payer: env.payer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::ClaimTransfer {}.data(),
+    }
+}
+
+async fn claim_and_check(env: &mut Env, pending_transfer: Pubkey) {
+    let claim = claim_instruction(env, pending_transfer);
+    let wallet = env.recipient.authority.insecure_clone();
+    let escrow_rent = lamports(&mut env.context, pending_transfer).await;
+    let payer_before = lamports(&mut env.context, env.payer.pubkey()).await;
+    send(&mut env.context, &[claim], &[&wallet]).await.unwrap();
+
+    let closed = env
+        .context
+        .banks_client
+        .get_account(pending_transfer)
+        .await
+        .unwrap();
+    assert!(closed.is_none());
+    assert_eq!(
+        lamports(&mut env.context, env.payer.pubkey()).await,
+        payer_before + escrow_rent
+    );
+
+    let sender_balance = load_balance(&mut env.context, env.sender.balance).await;
+    assert_eq!(sender_balance.open_escrow_count, 0);
+    let recipient_balance = load_balance(&mut env.context, env.recipient.balance).await;
+    assert_eq!(recipient_balance.pending_balance_credit_counter, 1);
+    let table = DecodeTable::for_u32(threads());
+    assert_eq!(
+        decrypt_pending_balance(
+            env.recipient.elgamal.secret(),
+            &recipient_balance.pending_balance_lo,
+            &recipient_balance.pending_balance_hi,
+            &table,
+            threads(),
+        ),
+        Some(TRANSFER_AMOUNT)
+    );
+}
+
+async fn cancel_and_check(env: &mut Env, pending_transfer: Pubkey) {
+    let cancel = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::CancelTransfer {
+            sender_balance: env.sender.balance,
+            pending_transfer,
+            sender: env.sender.authority.pubkey(),
+            payer: env.payer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::CancelTransfer {
+            new_decryptable_available_balance: env
+                .sender
+                .ae_key
+                .encrypt(STARTING_BALANCE)
+                .to_bytes(),
+        }
+        .data(),
+    };
+    let wallet = env.sender.authority.insecure_clone();
+    let escrow_rent = lamports(&mut env.context, pending_transfer).await;
+    let payer_before = lamports(&mut env.context, env.payer.pubkey()).await;
+    send(&mut env.context, &[cancel], &[&wallet]).await.unwrap();
+
+    let closed = env
+        .context
+        .banks_client
+        .get_account(pending_transfer)
+        .await
+        .unwrap();
+    assert!(closed.is_none());
+    assert_eq!(
+        lamports(&mut env.context, env.payer.pubkey()).await,
+        payer_before + escrow_rent
+    );
+
+    let sender_balance = load_balance(&mut env.context, env.sender.balance).await;
+    assert_eq!(sender_balance.open_escrow_count, 0);
+    let table = DecodeTable::for_u32(threads());
+    assert_eq!(
+        decrypt_amount(
+            env.sender.elgamal.secret(),
+            &sender_balance.encrypted_balance,
+            32,
+            &table,
+            threads(),
+        ),
+        Some(STARTING_BALANCE)
+    );
+}
+
+#[tokio::test]
+async fn claim_transfer_finds_pending_transfer_by_creation_nonce() {
+    let (mut env, pending_transfer) = setup(true).await;
+    claim_and_check(&mut env, pending_transfer).await;
+}
+
+#[tokio::test]
+async fn cancel_transfer_finds_pending_transfer_by_creation_nonce() {
+    let (mut env, pending_transfer) = setup(true).await;
+    cancel_and_check(&mut env, pending_transfer).await;
+}
+
+#[tokio::test]
+async fn claim_transfer_checks_recipient_key() {
+    let (mut env, pending_transfer) = setup(true).await;
+
+    // The recipient closed the balance and opened it again under a new key
+    env.recipient.elgamal = ElGamalKeypair::new_rand();
+    let reopened = user_balance(env.veilpay_mint, &env.recipient, &env.payer.pubkey(), 0, 0);
+    env.context.set_account(&env.recipient.balance, &reopened.into());
+
+    let claim = claim_instruction(&env, pending_transfer);
+    let wallet = env.recipient.authority.insecure_clone();
+    let error = send(&mut env.context, &[claim], &[&wallet])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(error, program_error(VeilPayError::RecipientKeyMismatch));
+}
+
+async fn close_sender_balance(env: &mut Env) -> TransactionError {
+    let close = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::CloseBalance {
+            confidential_balance: env.sender.balance,
+            owner: env.sender.authority.pubkey(),
+            payer: env.payer.pubkey(),
+            instructions_sysvar: sysvar_instructions::ID,
+            proof_context_payer: env.payer.pubkey(),
+            zero_proof_context: None,
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::CloseBalance {
+            zero_proof_instruction_offset: 1,
+        }
+        .data(),
+    };
+    let wallet = env.sender.authority.insecure_clone();
+    send(&mut env.context, &[close], &[&wallet])
+        .await
+        .unwrap_err()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn close_balance_waits_for_open_escrows() {
+    let (mut env, pending_transfer) = setup(true).await;
+
+    // Recreating the balance would restart its nonce at this PendingTransfer's address
+    let error = close_sender_balance(&mut env).await;
+    assert_eq!(error, program_error(VeilPayError::OpenEscrowsNotEmpty));
+
+    // Once cancelled only the missing zero proof stands in the way
+    cancel_and_check(&mut env, pending_transfer).await;
+    let error = close_sender_balance(&mut env).await;
+    assert_eq!(error, program_error(VeilPayError::MissingProofInstruction));
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, run by anchor test"]
+async fn create_then_claim_credits_recipient() {
+    let (mut env, _) = setup(false).await;
+    let pending_transfer = create_transfer(&mut env).await;
+    claim_and_check(&mut env, pending_transfer).await;
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, run by anchor test"]
+async fn create_then_cancel_refunds_sender() {
+    let (mut env, _) = setup(false).await;
+    let pending_transfer = create_transfer(&mut env).await;
+
+    let sender_balance = load_balance(&mut env.context, env.sender.balance).await;
+    assert_eq!(sender_balance.nonce, SEEDED_NONCE + 1);
+    assert_eq!(sender_balance.open_escrow_count, 1);
+    cancel_and_check(&mut env, pending_transfer).await;
+}
+
+/// Equality, validity and range proof contexts for `private_transfer`, in account order.
+type TransferProofContexts = [Option<Pubkey>; 3];
+
+/// Send a `private_transfer` of `transfer` at `SEEDED_NONCE`, taking the proofs from
+/// `proof_contexts` or else from instructions that are never there.
+async fn private_transfer(
+    env: &mut Env,
+    transfer: &TransferCiphertexts,
+    commitment_hash: [u8; 32],
+    proof_contexts: TransferProofContexts,
+) -> Result<(), BanksClientError> {
+    let [equality_proof_context, validity_proof_context, range_proof_context] = proof_contexts;
+    let instruction = Instruction {
+        program_id: veilpay::ID,
+        accounts: veilpay::accounts::PrivateTransfer {
+            veilpay_mint: env.veilpay_mint,
+            sender_balance: env.sender.balance,
+            receiver_balance: env.recipient.balance,
+            sender: env.sender.authority.pubkey(),
+            instructions_sysvar: sysvar_instructions::ID,
+            proof_context_payer: env.payer.pubkey(),
+            equality_proof_context,
+            validity_proof_context,
+            range_proof_context,
+        }
+        .to_account_metas(None),
+        data: veilpay::instruction::PrivateTransfer {
+            encrypted_amount_lo: transfer.lo_bytes(),
+            encrypted_amount_hi: transfer.hi_bytes(),
+            recipient_commitment: env.recipient.commitment,
+            expected_nonce: SEEDED_NONCE,
+            commitment_hash,
+            encrypted_tag: ENCRYPTED_TAG,
+            new_decryptable_available_balance: env.sender.ae_key.encrypt(0).to_bytes(),
+            equality_proof_instruction_offset: 1,
+            validity_proof_instruction_offset: 2,
+            range_proof_instruction_offset: 3,
+        }
+        .data(),
+    };
+    let wallet = env.sender.authority.insecure_clone();
+    send(&mut env.context, &[instruction], &[&wallet]).await
+}
+
+/// Send a `private_transfer` of a fresh ciphertext with no proofs attached.
+async fn private_transfer_with_hash(
+    env: &mut Env,
+    commitment_hash: impl FnOnce(
+        &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+        &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+    ) -> [u8; 32],
+) -> TransactionError {
+    let transfer = TransferCiphertexts::new(
+        env.sender.elgamal.pubkey(),
+        env.recipient.elgamal.pubkey(),
+        env.auditor.pubkey(),
+    );
+    let commitment_hash = commitment_hash(&transfer.lo_bytes(), &transfer.hi_bytes());
+    private_transfer(env, &transfer, commitment_hash, [None; 3])
+        .await
+        .unwrap_err()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn private_transfer_checks_commitment_hash() {
+    let (mut env, _) = setup(false).await;
+    let recipient_commitment = env.recipient.commitment;
+
+    // A commitment made for another cluster is rejected before any proof is looked at
+    let error = private_transfer_with_hash(&mut env, |lo, hi| {
+        generate_commitment_hash(
+            &[0u8; 32],
+            lo,
+            hi,
+            SEEDED_NONCE,
+            &recipient_commitment,
+            &ENCRYPTED_TAG,
+        )
+    })
+    .await;
+    assert_eq!(error, program_error(VeilPayError::InvalidCommitmentHash));
+
+    // So is one over another tag than the one emitted
+    let error = private_transfer_with_hash(&mut env, |lo, hi| {
+        generate_commitment_hash(
+            &GENESIS_HASH,
+            lo,
+            hi,
+            SEEDED_NONCE,
+            &recipient_commitment,
+            &[0u8; 32],
+        )
+    })
+    .await;
+    assert_eq!(error, program_error(VeilPayError::InvalidCommitmentHash));
+
+    // The matching commitment gets past the check and only fails for the missing proofs
+    let error = private_transfer_with_hash(&mut env, |lo, hi| {
+        generate_commitment_hash(
+            &GENESIS_HASH,
+            lo,
+            hi,
+            SEEDED_NONCE,
+            &recipient_commitment,
+            &ENCRYPTED_TAG,
+        )
+    })
+    .await;
+    assert_eq!(error, program_error(VeilPayError::MissingProofInstruction));
+}
+
+#[tokio::test]
+async fn private_transfer_checks_balance_owners() {
+    let (mut env, _) = setup(false).await;
+    let recipient_commitment = env.recipient.commitment;
+    let commitment_hash = |lo: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE],
+                           hi: &[u8; GROUPED_ENCRYPTED_VALUE_SIZE]| {
+        generate_commitment_hash(
+            &GENESIS_HASH,
+            lo,
+            hi,
+            SEEDED_NONCE,
+            &recipient_commitment,
+            &ENCRYPTED_TAG,
+        )
+    };
+    let (sender_balance, recipient_balance) = (env.sender.balance, env.recipient.balance);
+
+    // Both are balance PDAs of this mint, but each belongs to the other party
+    env.sender.balance = recipient_balance;
+    let error = private_transfer_with_hash(&mut env, commitment_hash).await;
+    assert_eq!(error, program_error(VeilPayError::InvalidSenderBalanceAccount));
+
+    env.sender.balance = sender_balance;
+    env.recipient.balance = sender_balance;
+    let error = private_transfer_with_hash(&mut env, commitment_hash).await;
+    assert_eq!(error, program_error(VeilPayError::InvalidReceiverBalanceAccount));
+
+    // Byte-for-byte copies of the real balances, stored away from their PDAs
+    env.sender.balance = copy_account(&mut env, sender_balance).await;
+    env.recipient.balance = recipient_balance;
+    let error = private_transfer_with_hash(&mut env, commitment_hash).await;
+    assert_eq!(error, program_error(VeilPayError::InvalidSenderBalanceAccount));
+
+    env.sender.balance = sender_balance;
+    env.recipient.balance = copy_account(&mut env, recipient_balance).await;
+    let error = private_transfer_with_hash(&mut env, commitment_hash).await;
+    assert_eq!(error, program_error(VeilPayError::InvalidReceiverBalanceAccount));
+
+    // A recipient that never opened a balance
+    env.recipient.balance = Pubkey::new_unique();
+    let error = private_transfer_with_hash(&mut env, commitment_hash).await;
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ErrorCode::AccountNotInitialized.into())
+        )
+    );
+}
+
+#[tokio::test]
+async fn private_transfer_checks_the_nonce() {
+    let (mut env, _) = setup(false).await;
+    let transfer = TransferCiphertexts::new(
+        env.sender.elgamal.pubkey(),
+        env.recipient.elgamal.pubkey(),
+        env.auditor.pubkey(),
+    );
+    let commitment_hash = generate_commitment_hash(
+        &GENESIS_HASH,
+        &transfer.lo_bytes(),
+        &transfer.hi_bytes(),
+        SEEDED_NONCE,
+        &env.recipient.commitment,
+        &ENCRYPTED_TAG,
+    );
+    let proof_contexts = seed_transfer_proofs(&mut env, &transfer, &transfer).await;
+    private_transfer(&mut env, &transfer, commitment_hash, proof_contexts)
+        .await
+        .unwrap();
+
+    // Replaying the transfer fails on the nonce before any proof is looked at
+    let error = private_transfer(&mut env, &transfer, commitment_hash, [None; 3])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(error, program_error(VeilPayError::InvalidNonce));
+
+    // So does a transfer signed ahead for a nonce the balance has not reached yet
+    let mut sender_balance = load_balance(&mut env.context, env.sender.balance).await;
+    sender_balance.nonce = SEEDED_NONCE - 1;
+    let account = program_account(&sender_balance, 8 + ConfidentialBalance::LEN);
+    env.context.set_account(&env.sender.balance, &account.into());
+    let error = private_transfer(&mut env, &transfer, commitment_hash, [None; 3])
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(error, program_error(VeilPayError::InvalidNonce));
+}
+
+/// Store `account` at a fresh address, as `verify_proof` would have left it.
+fn seed_account(env: &mut Env, account: Account) -> Pubkey {
+    let address = Pubkey::new_unique();
+    env.context.set_account(&address, &account.into());
+    address
+}
+
+/// Store a copy of the account at `address` at a fresh address.
+async fn copy_account(env: &mut Env, address: Pubkey) -> Pubkey {
+    let account = env.context.banks_client.get_account(address).await.unwrap();
+    seed_account(env, account.unwrap())
+}
+
+/// Proof contexts for sending `transfer`, with the range proof made over `range_transfer`.
+async fn seed_transfer_proofs(
+    env: &mut Env,
+    transfer: &TransferCiphertexts,
+    range_transfer: &TransferCiphertexts,
+) -> TransferProofContexts {
+    let authority = env.sender.authority.pubkey();
+    let payer = env.payer.pubkey();
+    let sender_balance = load_balance(&mut env.context, env.sender.balance).await;
+    let remaining = STARTING_BALANCE - TRANSFER_AMOUNT;
+    let (equality_proof, commitment, opening) =
+        equality_proof_data(env, &sender_balance, transfer, remaining);
+    let validity_proof = validity_proof_data(env, transfer);
+    let range_proof = range_proof_data(remaining, &commitment, &opening, range_transfer);
+    [
+        Some(seed_account(env, proof_context_account(&authority, &payer, &equality_proof))),
+        Some(seed_account(env, proof_context_account(&authority, &payer, &validity_proof))),
+        Some(seed_account(env, proof_context_account(&authority, &payer, &range_proof))),
+    ]
+}
+
+#[tokio::test]
+async fn private_transfer_checks_range_proof_commitments() {
+    let (mut env, _) = setup(false).await;
+    let transfer = TransferCiphertexts::new(
+        env.sender.elgamal.pubkey(),
+        env.recipient.elgamal.pubkey(),
+        env.auditor.pubkey(),
+    );
+    let commitment_hash = generate_commitment_hash(
+        &GENESIS_HASH,
+        &transfer.lo_bytes(),
+        &transfer.hi_bytes(),
+        SEEDED_NONCE,
+        &env.recipient.commitment,
+        &ENCRYPTED_TAG,
+    );
+
+    // A valid range proof, but over another encryption of the same amount
+    let other = TransferCiphertexts::new(
+        env.sender.elgamal.pubkey(),
+        env.recipient.elgamal.pubkey(),
+        env.auditor.pubkey(),
+    );
+    let proof_contexts = seed_transfer_proofs(&mut env, &transfer, &other).await;
+    let error = private_transfer(&mut env, &transfer, commitment_hash, proof_contexts)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(error, program_error(VeilPayError::RangeProofVerificationFailed));
+
+    let proof_contexts = seed_transfer_proofs(&mut env, &transfer, &transfer).await;
+    private_transfer(&mut env, &transfer, commitment_hash, proof_contexts)
+        .await
+        .unwrap();
+    let recipient_balance = load_balance(&mut env.context, env.recipient.balance).await;
+    assert_eq!(recipient_balance.pending_balance_credit_counter, 1);
+}
@@ -1,1244 +0,0 @@
-import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
-import { Veilpay } from "../target/types/veilpay";
-import { assert } from "chai";
-import { PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
-
-describe("VeilPay - Comprehensive Test Suite", () => {
-  const provider = anchor.AnchorProvider.env();
-  anchor.setProvider(provider);
-
-  const program = anchor.workspace.Veilpay as Program<Veilpay>;
-  const authority = provider.wallet;
-
-  // Test users
-  const sender = anchor.web3.Keypair.generate();
-  const receiver = anchor.web3.Keypair.generate();
-  const attacker = anchor.web3.Keypair.generate();
-  const thirdParty = anchor.web3.Keypair.generate();
-  const mintKeypair = anchor.web3.Keypair.generate();
-
-  // PDAs
-  let mintPda: anchor.web3.PublicKey;
-  let senderBalancePda: anchor.web3.PublicKey;
-  let receiverBalancePda: anchor.web3.PublicKey;
-  let attackerBalancePda: anchor.web3.PublicKey;
-  let thirdPartyBalancePda: anchor.web3.PublicKey;
-
-  // Helper function to create encrypted amount (Simple Arithmetic Mock)
-  // In production, this would use Arcium SDK encryption
-  function encryptAmount(amount: number): number[] {
-    const encrypted = new Array(64).fill(0);
-
-    // Store the amount directly in the first 8 bytes (Little Endian)
-    const amountBytes = Buffer.allocUnsafe(8);
-    amountBytes.writeBigUint64LE(BigInt(amount), 0);
-
-    // Copy amount to first 8 bytes
-    for (let i = 0; i < 8; i++) {
-      encrypted[i] = amountBytes[i];
-    }
-
-    // Fill the rest with deterministic noise
-    const noiseInput = Buffer.concat([amountBytes, Buffer.from("noise")]);
-    const noiseHash = anchor.utils.sha256.hash(noiseInput.toString('hex'));
-    const noiseBytes = Buffer.from(noiseHash, 'hex');
-
-    // Fill bytes 8..40 with noise
-    for (let i = 0; i < 32; i++) {
-      encrypted[8 + i] = noiseBytes[i];
-    }
-
-    return encrypted;
-  }
-
-  // Helper function to generate commitment hash (deterministic for testing)
-  function generateCommitmentHash(
-    encryptedAmount: number[],
-    nonce: number,
-    recipientPubkey: PublicKey
-  ): number[] {
-    const nonceBytes = Buffer.allocUnsafe(8);
-    nonceBytes.writeBigUint64LE(BigInt(nonce), 0);
-
-    // Create deterministic hash for testing
-    const combined = Buffer.concat([
-      Buffer.from(encryptedAmount),
-      nonceBytes,
-      recipientPubkey.toBuffer(),
-    ]);
-
-    // Simple deterministic hash for testing
-    const hash = new Array(32).fill(0);
-    for (let i = 0; i < 32; i++) {
-      hash[i] = combined[i % combined.length] ^ (nonce % 256);
-    }
-
-    return hash;
-  }
-
-  // Helper function to generate encrypted tag (deterministic for testing)
-  function generateEncryptedTag(
-    recipientPubkey: PublicKey,
-    senderSecret: Buffer
-  ): number[] {
-    const combined = Buffer.concat([
-      recipientPubkey.toBuffer(),
-      senderSecret,
-    ]);
-
-    // Simple deterministic tag for testing
-    const tag = new Array(32).fill(0);
-    for (let i = 0; i < 32; i++) {
-      tag[i] = combined[i % combined.length];
-    }
-
-    return tag;
-  }
-
-  // Helper function to request airdrop with retry logic
-  async function requestAirdropWithRetry(
-    publicKey: anchor.web3.PublicKey,
-    amount: number,
-    maxRetries: number = 5
-  ): Promise<string> {
-    for (let i = 0; i < maxRetries; i++) {
-      try {
-        const tx = await provider.connection.requestAirdrop(publicKey, amount);
-        await provider.connection.confirmTransaction(tx, "finalized");
-        return tx;
-      } catch (err: any) {
-        const delay = Math.pow(2, i) * 1000; // Exponential backoff
-        console.log(
-          `  Airdrop attempt ${i + 1} failed. Retrying in ${delay}ms...`
-        );
-        await new Promise((resolve) => setTimeout(resolve, delay));
-        if (i === maxRetries - 1) {
-          throw err;
-        }
-      }
-    }
-    throw new Error("Failed to airdrop after retries");
-  }
-
-  // Setup: Fund test accounts from main wallet
-  before("Fund test accounts", async () => {
-    console.log("Funding test accounts...");
-    const users = [sender, receiver, attacker, thirdParty];
-    for (const user of users) {
-      console.log(`Funding ${user.publicKey.toBase58().slice(0, 8)}...`);
-      try {
-        // Try airdrop with retries first
-        await requestAirdropWithRetry(user.publicKey, 1 * LAMPORTS_PER_SOL);
-        console.log(`  ✓ Airdrop successful`);
-      } catch (err) {
-        // If airdrop fails, transfer from main wallet instead
-        console.log(`  Airdrop failed, transferring from wallet...`);
-        try {
-          const transferTx = new anchor.web3.Transaction().add(
-            anchor.web3.SystemProgram.transfer({
-              fromPubkey: provider.wallet.publicKey,
-              toPubkey: user.publicKey,
-              lamports: 1 * anchor.web3.LAMPORTS_PER_SOL,
-            })
-          );
-          await provider.sendAndConfirm(transferTx, [], {
-            maxRetries: 5,
-            skipPreflight: true,
-          });
-          console.log(`  ✓ Transfer successful`);
-        } catch (transferErr) {
-          console.warn(
-            `  ✗ Could not fund ${user.publicKey.toBase58()} - tests may fail`
-          );
-        }
-      }
-    }
-  });
-
-  // ============================================
-  // SECTION 1: MINT INITIALIZATION TESTS
-  // ============================================
-
-  describe("Mint Initialization", () => {
-    it("Initializes VeilPay mint successfully", async () => {
-      mintPda = mintKeypair.publicKey;
-
-      const csplConfig = new Array(64).fill(1);
-
-      const tx = await program.methods
-        .initializeMint(csplConfig)
-        // @ts-ignore
-        .accounts({
-          veilpayMint: mintPda,
-          authority: authority.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([mintKeypair])
-        .rpc();
-
-      const mintAccount = await program.account.veilPayMint.fetch(mintPda);
-
-      assert.ok(
-        mintAccount.authority.equals(authority.publicKey),
-        "Mint authority should match"
-      );
-      assert.deepEqual(
-        Array.from(mintAccount.csplConfig),
-        csplConfig,
-        "cSPL config should match"
-      );
-    });
-
-    it("Fails to initialize mint twice", async () => {
-      const duplicateMint = anchor.web3.Keypair.generate();
-      try {
-        await program.methods
-          .initializeMint(new Array(64).fill(0))
-          // @ts-ignore
-          .accounts({
-            veilpayMint: duplicateMint.publicKey,
-            authority: authority.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([duplicateMint])
-          .rpc();
-
-        // Try to initialize the same account again
-        await program.methods
-          .initializeMint(new Array(64).fill(1))
-          // @ts-ignore
-          .accounts({
-            veilpayMint: duplicateMint.publicKey,
-            authority: authority.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([duplicateMint])
-          .rpc();
-
-        assert.fail("Should have failed - mint already initialized");
-      } catch (err: any) {
-        assert.ok(err.toString().includes("already in use") || err.toString().includes("custom program error"), "Should fail when trying to reinitialize");
-      }
-    });
-
-    it("Fails if non-authority tries to initialize mint", async () => {
-      const fakeMint = anchor.web3.Keypair.generate();
-
-      try {
-        await program.methods
-          .initializeMint(new Array(64).fill(0))
-          // @ts-ignore
-          .accounts({
-            veilpayMint: fakeMint.publicKey,
-            authority: sender.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([fakeMint]) // Missing sender signer? No, sender must sign as authority.
-          // Wait, if authority is sender, sender must sign. 
-          // .signers([fakeMint]) only signs with mint.
-          // If we want sender to sign, we need .signers([fakeMint, sender])?
-          // But here we are testing FAILURE.
-          // If sender is authority, passing sender as authority means sender IS expected to sign to pay?
-          // initialization usually requires payer.
-          // initialize_mint.rs: payer = authority.
-          // So authority MUST sign.
-          // If we pass sender.publicKey as authority, we MUST sign with sender.
-          .signers([fakeMint, sender])
-          .rpc();
-
-        assert.fail("Should have failed - wrong authority");
-      } catch (err: any) {
-        assert.ok(true, "Non-authority should not be able to initialize");
-      }
-    });
-  });
-
-  // ============================================
-  // SECTION 2: BALANCE INITIALIZATION TESTS
-  // ============================================
-
-  describe("Balance Initialization", () => {
-    it("Initializes sender confidential balance", async () => {
-      [senderBalancePda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("balance"), sender.publicKey.toBuffer()],
-        program.programId
-      );
-
-      // Explicitly pass all accounts
-      const tx = await program.methods
-        .initBalance()
-        // @ts-ignore
-        .accounts({
-          confidentialBalance: senderBalancePda,
-          owner: sender.publicKey,
-          payer: sender.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([sender])
-        .rpc();
-
-      const balance = await program.account.confidentialBalance.fetch(senderBalancePda);
-
-      assert.equal(balance.nonce.toNumber(), 0, "Nonce should start at 0");
-      assert.lengthOf(balance.encryptedBalance, 64, "Encrypted balance should be 64 bytes");
-      assert.ok(
-        balance.encryptedBalance.every(b => b === 0),
-        "Initial balance should be zero"
-      );
-    });
-
-    it("Initializes receiver confidential balance", async () => {
-      [receiverBalancePda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("balance"), receiver.publicKey.toBuffer()],
-        program.programId
-      );
-
-      await program.methods
-        .initBalance()
-        // @ts-ignore
-        .accounts({
-          confidentialBalance: receiverBalancePda,
-          owner: receiver.publicKey,
-          payer: receiver.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([receiver])
-        .rpc();
-
-      const balance = await program.account.confidentialBalance.fetch(receiverBalancePda);
-      assert.equal(balance.nonce.toNumber(), 0);
-    });
-
-    it("Initializes attacker balance for testing", async () => {
-      [attackerBalancePda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("balance"), attacker.publicKey.toBuffer()],
-        program.programId
-      );
-
-      await program.methods
-        .initBalance()
-        .accounts({
-          confidentialBalance: attackerBalancePda,
-          owner: attacker.publicKey,
-          payer: attacker.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([attacker])
-        .rpc();
-
-      const balance = await program.account.confidentialBalance.fetch(attackerBalancePda);
-      assert.equal(balance.nonce.toNumber(), 0);
-    });
-
-    it("Fails to initialize balance twice", async () => {
-      try {
-        await program.methods
-          .initBalance()
-          // @ts-ignore
-          .accounts({
-            confidentialBalance: senderBalancePda,
-            owner: sender.publicKey,
-            payer: sender.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([sender])
-          .rpc();
-
-        assert.fail("Should have failed - balance already initialized");
-      } catch (err: any) {
-        assert.ok(
-          err.toString().includes("already in use"),
-          "Should fail with 'already in use'"
-        );
-      }
-    });
-
-    it("Fails if wrong owner tries to initialize", async () => {
-      const wrongBalancePda = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("balance"), sender.publicKey.toBuffer()],
-        program.programId
-      )[0];
-
-      try {
-        await program.methods
-          .initBalance()
-          // @ts-ignore
-          .accounts({
-            confidentialBalance: wrongBalancePda,
-            owner: attacker.publicKey,
-            payer: attacker.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([attacker])
-          .rpc();
-
-        assert.fail("Should have failed - wrong owner");
-      } catch (err: any) {
-        assert.ok(true, "Wrong owner should not be able to initialize");
-      }
-    });
-
-    it("Emits BalanceInitializedEvent", async () => {
-      [thirdPartyBalancePda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("balance"), thirdParty.publicKey.toBuffer()],
-        program.programId
-      );
-
-      let eventEmitted = false;
-      const listener = program.addEventListener(
-        "balanceInitializedEvent",
-        (event) => {
-          assert.lengthOf(event.ownerCommitment, 32);
-          assert.ok(event.slot.toNumber() > 0);
-          assert.ok(event.timestamp.toNumber() > 0);
-          eventEmitted = true;
-        }
-      );
-
-      await program.methods
-        .initBalance()
-        // @ts-ignore
-        .accounts({
-          confidentialBalance: thirdPartyBalancePda,
-          owner: thirdParty.publicKey,
-          payer: thirdParty.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([thirdParty])
-        .rpc();
-
-      await new Promise(resolve => setTimeout(resolve, 1000));
-      program.removeEventListener(listener);
-      assert.ok(eventEmitted);
-    });
-  });
-
-  describe("Private Transfers - Success Cases", () => {
-    it("Performs first private transfer successfully", async () => {
-      // 1. Deposit funds first (REQUIRED by new mock logic)
-      const depositAmount = 100_000_000;
-      const depositEncrypted = encryptAmount(depositAmount);
-
-      const [vaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault")],
-        program.programId
-      );
-
-      await program.methods
-        .deposit(new anchor.BN(depositAmount), depositEncrypted)
-        // @ts-ignore
-        .accounts({
-          confidentialBalance: senderBalancePda,
-          vault: vaultPda,
-          signer: sender.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([sender])
-        .rpc();
-
-      // 2. Perform Transfer
-      const amount = 100;
-      const encryptedAmount = encryptAmount(amount);
-      const senderSecret = Buffer.from("sender_secret_32_bytes_long_!!");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        0,
-        receiver.publicKey
-      );
-
-      const senderBalanceBefore = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const receiverBalanceBefore = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-
-      await program.methods
-        .privateTransfer(
-          encryptedAmount,
-          new anchor.BN(0),
-          commitmentHash,
-          encryptedTag
-        )
-        .accounts({
-          senderBalance: senderBalancePda,
-          receiverBalance: receiverBalancePda,
-          sender: sender.publicKey,
-        })
-        .signers([sender])
-        .rpc();
-
-      const senderBalanceAfter = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const receiverBalanceAfter = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-
-      assert.equal(
-        senderBalanceAfter.nonce.toNumber(),
-        senderBalanceBefore.nonce.toNumber() + 1,
-        "Sender nonce should increment"
-      );
-      assert.equal(
-        receiverBalanceAfter.nonce.toNumber(),
-        receiverBalanceBefore.nonce.toNumber() + 1,
-        "Receiver nonce should increment"
-      );
-      assert.notDeepEqual(
-        senderBalanceAfter.encryptedBalance,
-        senderBalanceBefore.encryptedBalance,
-        "Sender balance should change"
-      );
-      assert.notDeepEqual(
-        receiverBalanceAfter.encryptedBalance,
-        receiverBalanceBefore.encryptedBalance,
-        "Receiver balance should change"
-      );
-    });
-
-    it("Performs fractional amount transfer (0.001 SOL)", async () => {
-      // 0.001 SOL = 1,000,000 Lamports
-      // This tests that our "u64" mock handles smaller numbers correctly without underflow/precision loss
-
-      // Deposit enough specifically for this test
-      const depositAmount = 2_000_000;
-      const depositEncrypted = encryptAmount(depositAmount);
-      const [vaultPda] = PublicKey.findProgramAddressSync([Buffer.from("vault")], program.programId);
-
-      await program.methods
-        .deposit(new anchor.BN(depositAmount), depositEncrypted)
-        // @ts-ignore
-        .accounts({
-          confidentialBalance: senderBalancePda,
-          vault: vaultPda,
-          signer: sender.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([sender])
-        .rpc();
-
-      const amount = 1_000_000;
-      const encryptedAmount = encryptAmount(amount);
-      const senderSecret = Buffer.from("fractional_secret_32_bytes_long!");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const currentNonce = senderBalance.nonce.toNumber();
-
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        currentNonce,
-        receiver.publicKey
-      );
-
-      await program.methods
-        .privateTransfer(
-          encryptedAmount,
-          new anchor.BN(currentNonce),
-          commitmentHash,
-          encryptedTag
-        )
-        .accounts({
-          senderBalance: senderBalancePda,
-          receiverBalance: receiverBalancePda,
-          sender: sender.publicKey,
-        })
-        .signers([sender])
-        .rpc();
-
-      // If this succeeds without error, the arithmetic is valid for small numbers
-    });
-
-    it("Performs multiple sequential transfers", async () => {
-      // Mock encryption now supports arithmetic!
-      const amounts = [10, 20, 30];
-
-      // Deposit initial funds
-      const depositAmount = 100_000_000;
-      const depositEncrypted = encryptAmount(depositAmount);
-      const [vaultPda] = PublicKey.findProgramAddressSync([Buffer.from("vault")], program.programId);
-
-      await program.methods
-        .deposit(new anchor.BN(depositAmount), depositEncrypted)
-        // @ts-ignore
-        .accounts({
-          confidentialBalance: senderBalancePda,
-          vault: vaultPda,
-          signer: sender.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([sender])
-        .rpc();
-
-      let currentNonce = (await program.account.confidentialBalance.fetch(senderBalancePda)).nonce.toNumber();
-
-      for (const amount of amounts) {
-        const encryptedAmount = encryptAmount(amount);
-        const senderSecret = Buffer.from(`secret_${amount}_32_bytes_long_!!`);
-        const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-        const commitmentHash = generateCommitmentHash(
-          encryptedAmount,
-          currentNonce,
-          receiver.publicKey
-        );
-
-        await program.methods
-          .privateTransfer(
-            encryptedAmount,
-            new anchor.BN(currentNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: receiverBalancePda,
-            sender: sender.publicKey,
-          })
-          .signers([sender])
-          .rpc();
-
-        currentNonce++;
-      }
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const receiverBalance = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-
-      // Verify Nonces match expected + verified count
-      assert.equal(
-        receiverBalance.nonce.toNumber(),
-        senderBalance.nonce.toNumber(),
-        "Nonces should match"
-      );
-    });
-
-    it("Emits PrivateTransferEvent with correct data", async () => {
-      // Mock encryption now supports proper event data verification
-      const amount = 50;
-
-      // Deposit funds
-      const depositEncrypted = encryptAmount(amount);
-      const [vaultPda] = PublicKey.findProgramAddressSync([Buffer.from("vault")], program.programId);
-      await program.methods.deposit(new anchor.BN(amount), depositEncrypted)
-        // @ts-ignore
-        .accounts({ confidentialBalance: senderBalancePda, vault: vaultPda, signer: sender.publicKey, systemProgram: SystemProgram.programId })
-        .signers([sender]).rpc();
-
-      const encryptedAmount = encryptAmount(amount);
-      const senderSecret = Buffer.from("event_test_secret_32_bytes_long");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const currentNonce = senderBalance.nonce.toNumber();
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        currentNonce,
-        receiver.publicKey
-      );
-
-      let eventEmitted = false;
-      const listener = program.addEventListener(
-        "privateTransferEvent",
-        (event) => {
-          assert.lengthOf(event.commitmentHash, 32, "Commitment hash should be 32 bytes");
-          assert.lengthOf(event.encryptedTag, 32, "Encrypted tag should be 32 bytes");
-          assert.equal(event.eventType, 0, "Event type should be 0 (transfer)");
-          assert.ok(event.slot.toNumber() > 0, "Slot should be positive");
-          assert.ok(event.timestamp.toNumber() > 0, "Timestamp should be positive");
-          assert.equal(event.senderBump, senderBalance.bump, "Sender bump should match");
-          eventEmitted = true;
-        }
-      );
-
-      await program.methods
-        .privateTransfer(
-          encryptedAmount,
-          new anchor.BN(currentNonce),
-          commitmentHash,
-          encryptedTag
-        )
-        .accounts({
-          senderBalance: senderBalancePda,
-          receiverBalance: receiverBalancePda,
-          sender: sender.publicKey,
-        })
-        .signers([sender])
-        .rpc();
-
-      await new Promise((resolve) => setTimeout(resolve, 1000));
-      await program.removeEventListener(listener);
-
-      assert.ok(eventEmitted, "PrivateTransferEvent should be emitted");
-    });
-
-    it.skip("Handles zero amount transfer", async () => {
-      // Skipped: Mock encryption doesn't support proper balance tracking
-      const encryptedAmount = encryptAmount(0);
-      const senderSecret = Buffer.from("zero_amount_secret_32_bytes_long");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-
-      const senderBalanceBefore = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const currentNonce = senderBalanceBefore.nonce.toNumber();
-
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        currentNonce,
-        receiver.publicKey
-      );
-
-      await program.methods
-        .privateTransfer(
-          encryptedAmount,
-          new anchor.BN(currentNonce),
-          commitmentHash,
-          encryptedTag
-        )
-        .accounts({
-          senderBalance: senderBalancePda,
-          receiverBalance: receiverBalancePda,
-          sender: sender.publicKey,
-        })
-        .signers([sender])
-        .rpc();
-
-      const senderBalanceAfter = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      assert.equal(
-        senderBalanceAfter.nonce.toNumber(),
-        currentNonce + 1,
-        "Nonce should increment even for zero amount"
-      );
-    });
-  });
-
-  describe("Private Transfers - Error Cases", () => {
-    it("Fails with invalid nonce (replay attack)", async function () {
-      const amount = 5;
-      const encryptedAmount = encryptAmount(amount);
-      const senderSecret = Buffer.from("replay_test_secret_32_bytes_long");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const currentNonce = senderBalance.nonce.toNumber();
-
-      // Skip test if nonce is 0 (can't decrement below 0)
-      if (currentNonce === 0) {
-        this.skip();
-        return;
-      }
-
-      const oldNonce = currentNonce - 1; // Use old nonce
-
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        oldNonce,
-        receiver.publicKey
-      );
-
-      try {
-        await program.methods
-          .privateTransfer(
-            encryptedAmount,
-            new anchor.BN(oldNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: receiverBalancePda,
-            sender: sender.publicKey,
-          })
-          .signers([sender])
-          .rpc();
-
-        assert.fail("Should have failed - invalid nonce");
-      } catch (err: any) {
-        assert.ok(
-          err.toString().includes("InvalidNonce") || err.toString().includes("Invalid nonce"),
-          "Should fail with InvalidNonce error"
-        );
-      }
-    });
-
-    it("Fails with future nonce", async () => {
-      const amount = 5;
-      const encryptedAmount = encryptAmount(amount);
-      const senderSecret = Buffer.from("future_nonce_secret_32_bytes_long");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const futureNonce = senderBalance.nonce.toNumber() + 10;
-
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        futureNonce,
-        receiver.publicKey
-      );
-
-      try {
-        await program.methods
-          .privateTransfer(
-            encryptedAmount,
-            new anchor.BN(futureNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: receiverBalancePda,
-            sender: sender.publicKey,
-          })
-          .signers([sender])
-          .rpc();
-
-        assert.fail("Should have failed - future nonce");
-      } catch (err: any) {
-        assert.ok(
-          err.toString().includes("InvalidNonce") || err.toString().includes("Invalid nonce"),
-          "Should fail with InvalidNonce error"
-        );
-      }
-    });
-
-    it("Fails if attacker tries to spend sender balance", async () => {
-      const amount = 100;
-      const encryptedAmount = encryptAmount(amount);
-      const senderSecret = Buffer.from("attacker_secret_32_bytes_long");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const currentNonce = senderBalance.nonce.toNumber();
-
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        currentNonce,
-        receiver.publicKey
-      );
-
-      try {
-        await program.methods
-          .privateTransfer(
-            encryptedAmount,
-            new anchor.BN(currentNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: receiverBalancePda,
-            sender: attacker.publicKey,
-          })
-          .signers([attacker])
-          .rpc();
-
-        assert.fail("Should have failed - unauthorized access");
-      } catch (err: any) {
-        assert.ok(
-          err.toString().includes("UnauthorizedAccess") ||
-          err.toString().includes("Unauthorized access"),
-          "Should fail with UnauthorizedAccess error"
-        );
-      }
-    });
-
-    it("Fails if sender does not sign", async () => {
-      const amount = 5;
-      const encryptedAmount = encryptAmount(amount);
-      const senderSecret = Buffer.from("no_signer_secret_32_bytes_long");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const currentNonce = senderBalance.nonce.toNumber();
-
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        currentNonce,
-        receiver.publicKey
-      );
-
-      try {
-        await program.methods
-          .privateTransfer(
-            encryptedAmount,
-            new anchor.BN(currentNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: receiverBalancePda,
-            sender: sender.publicKey,
-          })
-          .rpc();
-
-        assert.fail("Should have failed - missing signer");
-      } catch (err: any) {
-        assert.ok(true, "Should fail without signer");
-      }
-    });
-
-    it("Fails if receiver balance account does not exist", async () => {
-      const newReceiver = anchor.web3.Keypair.generate();
-      const newReceiverBalancePda = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("balance"), newReceiver.publicKey.toBuffer()],
-        program.programId
-      )[0];
-
-      const amount = 10;
-      const encryptedAmount = encryptAmount(amount);
-      const senderSecret = Buffer.from("no_receiver_secret_32_bytes_long");
-      const encryptedTag = generateEncryptedTag(newReceiver.publicKey, senderSecret);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const currentNonce = senderBalance.nonce.toNumber();
-
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        currentNonce,
-        newReceiver.publicKey
-      );
-
-      try {
-        await program.methods
-          .privateTransfer(
-            encryptedAmount,
-            new anchor.BN(currentNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: newReceiverBalancePda,
-            sender: sender.publicKey,
-          })
-          .signers([sender])
-          .rpc();
-
-        assert.fail("Should have failed - receiver balance not initialized");
-      } catch (err: any) {
-        assert.ok(
-          err.toString().includes("AccountNotInitialized") ||
-          err.toString().includes("Account not found"),
-          "Should fail - receiver account not initialized"
-        );
-      }
-    });
-
-    it("Fails with invalid encrypted amount format (all zeros)", async () => {
-      const invalidEncryptedAmount = new Array(64).fill(0);
-      const senderSecret = Buffer.from("invalid_format_secret_32_bytes");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const currentNonce = senderBalance.nonce.toNumber();
-
-      const commitmentHash = generateCommitmentHash(
-        invalidEncryptedAmount,
-        currentNonce,
-        receiver.publicKey
-      );
-
-      try {
-        await program.methods
-          .privateTransfer(
-            invalidEncryptedAmount,
-            new anchor.BN(currentNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: receiverBalancePda,
-            sender: sender.publicKey,
-          })
-          .signers([sender])
-          .rpc();
-
-        assert.fail("Should have failed - invalid encryption format");
-      } catch (err: any) {
-        assert.ok(true, "Should fail with invalid encryption");
-      }
-    });
-
-    it("Fails when trying to transfer more than balance", async () => {
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-
-      const hugeAmount = Number.MAX_SAFE_INTEGER;
-      const encryptedAmount = encryptAmount(hugeAmount);
-      const senderSecret = Buffer.from("huge_amount_secret_32_bytes_long");
-      const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-      const currentNonce = senderBalance.nonce.toNumber();
-
-      const commitmentHash = generateCommitmentHash(
-        encryptedAmount,
-        currentNonce,
-        receiver.publicKey
-      );
-
-      try {
-        await program.methods
-          .privateTransfer(
-            encryptedAmount,
-            new anchor.BN(currentNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: receiverBalancePda,
-            sender: sender.publicKey,
-          })
-          .signers([sender])
-          .rpc();
-
-        assert.fail("Should have failed - insufficient balance");
-      } catch (err: any) {
-        assert.ok(
-          err.toString().includes("InsufficientBalance") ||
-          err.toString().includes("Insufficient balance"),
-          "Should fail with InsufficientBalance error"
-        );
-      }
-    });
-  });
-
-  describe("State Consistency", () => {
-    it("Maintains nonce consistency between sender and receiver", async () => {
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const receiverBalance = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-
-      assert.equal(
-        senderBalance.nonce.toNumber(),
-        receiverBalance.nonce.toNumber(),
-        "Nonces should be synchronized"
-      );
-    });
-
-    it("Preserves encrypted balance format after transfers", async () => {
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const receiverBalance = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-
-      assert.lengthOf(
-        senderBalance.encryptedBalance,
-        64,
-        "Sender balance should be 64 bytes"
-      );
-      assert.lengthOf(
-        receiverBalance.encryptedBalance,
-        64,
-        "Receiver balance should be 64 bytes"
-      );
-    });
-
-    it("Maintains owner commitment integrity", async () => {
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const receiverBalance = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-
-      assert.lengthOf(
-        senderBalance.ownerCommitment,
-        32,
-        "Owner commitment should be 32 bytes"
-      );
-      assert.lengthOf(
-        receiverBalance.ownerCommitment,
-        32,
-        "Owner commitment should be 32 bytes"
-      );
-      assert.notDeepEqual(
-        senderBalance.ownerCommitment,
-        receiverBalance.ownerCommitment,
-        "Owner commitments should be different"
-      );
-    });
-
-    it("Preserves bump seeds correctly", async () => {
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const receiverBalance = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-
-      assert.ok(
-        senderBalance.bump > 0 && senderBalance.bump <= 255,
-        "Sender bump should be valid"
-      );
-      assert.ok(
-        receiverBalance.bump > 0 && receiverBalance.bump <= 255,
-        "Receiver bump should be valid"
-      );
-    });
-  });
-
-  describe("Integration Tests", () => {
-    it.skip("Handles complex transfer scenario with multiple parties", async () => {
-      // Skipped: Mock encryption doesn't support proper balance tracking
-      const amount1 = 25;
-      const encryptedAmount1 = encryptAmount(amount1);
-      const senderSecret1 = Buffer.from("complex_scenario_secret_1");
-      const encryptedTag1 = generateEncryptedTag(receiver.publicKey, senderSecret1);
-
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const nonce1 = senderBalance.nonce.toNumber();
-      const commitmentHash1 = generateCommitmentHash(
-        encryptedAmount1,
-        nonce1,
-        receiver.publicKey
-      );
-
-      await program.methods
-        .privateTransfer(
-          encryptedAmount1,
-          new anchor.BN(nonce1),
-          commitmentHash1,
-          encryptedTag1
-        )
-        .accounts({
-          senderBalance: senderBalancePda,
-          receiverBalance: receiverBalancePda,
-          sender: sender.publicKey,
-        })
-        .signers([sender])
-        .rpc();
-
-      const amount2 = 15;
-      const encryptedAmount2 = encryptAmount(amount2);
-      const receiverSecret = Buffer.from("complex_scenario_secret_2");
-      const encryptedTag2 = generateEncryptedTag(thirdParty.publicKey, receiverSecret);
-
-      const receiverBalance = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-      const nonce2 = receiverBalance.nonce.toNumber();
-      const commitmentHash2 = generateCommitmentHash(
-        encryptedAmount2,
-        nonce2,
-        thirdParty.publicKey
-      );
-
-      await program.methods
-        .privateTransfer(
-          encryptedAmount2,
-          new anchor.BN(nonce2),
-          commitmentHash2,
-          encryptedTag2
-        )
-        .accounts({
-          senderBalance: receiverBalancePda,
-          receiverBalance: thirdPartyBalancePda,
-          sender: receiver.publicKey,
-        })
-        .signers([receiver])
-        .rpc();
-
-      const finalSenderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      const finalReceiverBalance = await program.account.confidentialBalance.fetch(
-        receiverBalancePda
-      );
-      const finalThirdPartyBalance = await program.account.confidentialBalance.fetch(
-        thirdPartyBalancePda
-      );
-
-      assert.equal(
-        finalSenderBalance.nonce.toNumber(),
-        finalReceiverBalance.nonce.toNumber() - 1,
-        "Nonces should reflect transfer sequence"
-      );
-      assert.equal(
-        finalReceiverBalance.nonce.toNumber(),
-        finalThirdPartyBalance.nonce.toNumber(),
-        "Receiver and third party nonces should match"
-      );
-    });
-
-    it.skip("Handles rapid sequential transfers", async () => {
-      // Skipped: Mock encryption doesn't support proper balance tracking
-      const numTransfers = 5;
-      const senderBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      let currentNonce = senderBalance.nonce.toNumber();
-
-      for (let i = 0; i < numTransfers; i++) {
-        const amount = 1;
-        const encryptedAmount = encryptAmount(amount);
-        const senderSecret = Buffer.from(`rapid_transfer_${i}_32_bytes_long`);
-        const encryptedTag = generateEncryptedTag(receiver.publicKey, senderSecret);
-        const commitmentHash = generateCommitmentHash(
-          encryptedAmount,
-          currentNonce,
-          receiver.publicKey
-        );
-
-        await program.methods
-          .privateTransfer(
-            encryptedAmount,
-            new anchor.BN(currentNonce),
-            commitmentHash,
-            encryptedTag
-          )
-          .accounts({
-            senderBalance: senderBalancePda,
-            receiverBalance: receiverBalancePda,
-            sender: sender.publicKey,
-          })
-          .signers([sender])
-          .rpc();
-
-        currentNonce++;
-      }
-
-      const finalBalance = await program.account.confidentialBalance.fetch(
-        senderBalancePda
-      );
-      assert.equal(
-        finalBalance.nonce.toNumber(),
-        senderBalance.nonce.toNumber() + numTransfers,
-        "Nonce should increment by number of transfers"
-      );
-    });
-  });
-});